@@ -27,7 +27,7 @@ const SPACE: u8 = ' ' as u32 as u8;
 #[allow(clippy::cast_possible_truncation)]
 const TAB: u8 = '\t' as u32 as u8;
 #[allow(clippy::cast_possible_truncation)]
-const FORM_FEED: u8 = '\x0C' as u32 as u8;
+pub(crate) const FORM_FEED: u8 = '\x0C' as u32 as u8;
 
 #[allow(clippy::cast_possible_truncation)]
 pub(crate) const LINE_FEED: u8 = '\n' as u32 as u8;
@@ -0,0 +1,22 @@
+pub(crate) mod binary;
+pub(crate) mod check;
+pub(crate) mod decode;
+pub(crate) mod diff;
+pub(crate) mod git;
+pub(crate) mod in_place;
+pub(crate) mod input;
+pub(crate) mod lines;
+pub(crate) mod lsp;
+pub(crate) mod pool;
+pub(crate) mod records;
+pub(crate) mod stats;
+pub(crate) mod stream;
+pub(crate) mod walk;
+pub(crate) mod watch;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(test)]
+#[path = "test_inputs.rs"]
+mod test_inputs;
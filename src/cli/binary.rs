@@ -0,0 +1,48 @@
+/// How many leading bytes are inspected to detect binary files.
+const SAMPLE_LEN: usize = 8192;
+
+/// Within the sample, the share of bytes that may be invalid UTF-8 before a file counts as binary.
+const MAX_INVALID_RATIO: f64 = 0.3;
+
+/// A stand-in for undecodable bytes that sifting treats as content.
+const PLACEHOLDER: char = '?';
+
+/// Whether these bytes look like a binary file: they have `NUL` bytes, unless `is_nul_text` is set, or mostly invalid UTF-8.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn is_binary(bytes: &[u8], is_nul_text: bool) -> bool {
+    let sample: &[u8] = &bytes[..bytes.len().min(SAMPLE_LEN)];
+    if !is_nul_text && sample.contains(&0) {
+        return true;
+    }
+    let invalid_len: usize = sample
+        .utf8_chunks()
+        .map(|chunk| chunk.invalid().len())
+        .sum();
+    invalid_len as f64 > sample.len() as f64 * MAX_INVALID_RATIO
+}
+
+/// Replace every undecodable byte with a placeholder, keeping the length and whitespaces intact.
+pub(crate) fn to_placeholder_text(bytes: &[u8]) -> String {
+    let mut text: String = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        text.extend(std::iter::repeat(PLACEHOLDER).take(chunk.invalid().len()));
+    }
+    text
+}
+
+/// Map the sifted placeholder text back onto the original bytes.
+/// This works because sifting only removes whitespaces, so `sifted` is a subsequence of `text`.
+pub(crate) fn restore_invalid_bytes(original: &[u8], text: &str, sifted: &str) -> Vec<u8> {
+    let text: &[u8] = text.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(sifted.len());
+    let mut ind: usize = 0;
+    for byte in sifted.bytes() {
+        while text[ind] != byte {
+            ind += 1;
+        }
+        out.push(original[ind]);
+        ind += 1;
+    }
+    out
+}
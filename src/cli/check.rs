@@ -0,0 +1,8 @@
+/// The 1-based line and column of the `char` at byte `offset` of `text`.
+pub(crate) fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before: &str = &text[..offset];
+    let line_start: usize = before.rfind('\n').map_or(0, |ind: usize| ind + 1);
+    let line: usize = before.matches('\n').count() + 1;
+    let column: usize = before[line_start..].chars().count() + 1;
+    (line, column)
+}
@@ -0,0 +1,111 @@
+use crate::cli::binary::{is_binary, restore_invalid_bytes, to_placeholder_text};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::borrow::Cow;
+
+/// The encoding of the inputs, as given to `--encoding`.
+#[derive(Clone, Copy)]
+pub(crate) enum EncodingArg {
+    /// Detect UTF-8 and UTF-16 by their byte order mark, falling back to UTF-8.
+    Auto,
+    Label(&'static Encoding),
+}
+
+/// Parse `auto` or a [WHATWG encoding label](https://encoding.spec.whatwg.org/#names-and-labels).
+pub(crate) fn parse_encoding(label: &str) -> Result<EncodingArg, String> {
+    if label.eq_ignore_ascii_case("auto") {
+        return Ok(EncodingArg::Auto);
+    }
+    Encoding::for_label(label.as_bytes())
+        .map(EncodingArg::Label)
+        .ok_or_else(|| format!("unknown encoding `{label}`"))
+}
+
+/// An input decoded to text, remembering how to turn the sifted text back into bytes.
+/// UTF-8 inputs are borrowed rather than copied.
+pub(crate) struct Decoded<'a> {
+    pub(crate) text: Cow<'a, str>,
+    original: Original<'a>,
+}
+
+enum Original<'a> {
+    Utf8,
+    /// UTF-8 with undecodable bytes, replaced by placeholders in the text.
+    Lossy(&'a [u8]),
+    Encoded {
+        encoding: &'static Encoding,
+        bom: &'a [u8],
+    },
+}
+
+/// Decode an input, or explain why it is skipped.
+/// `NUL` bytes only mark an input as binary if `is_nul_text` is not set.
+pub(crate) fn decode(
+    bytes: &[u8],
+    encoding: Option<EncodingArg>,
+    lossy: bool,
+    is_nul_text: bool,
+) -> Result<Decoded<'_>, String> {
+    let (encoding, bom_len): (&'static Encoding, usize) = match encoding {
+        None => (UTF_8, 0),
+        Some(EncodingArg::Auto) => Encoding::for_bom(bytes).unwrap_or((UTF_8, 0)),
+        Some(EncodingArg::Label(encoding)) => match Encoding::for_bom(bytes) {
+            Some((bom_encoding, bom_len)) if bom_encoding == encoding => (encoding, bom_len),
+            _ => (encoding, 0),
+        },
+    };
+    if encoding != UTF_8 {
+        let body: &[u8] = &bytes[bom_len..];
+        // Undecodable bytes could not be encoded back, so `--lossy` only applies to UTF-8.
+        let text: Cow<str> = encoding
+            .decode_without_bom_handling_and_without_replacement(body)
+            .ok_or_else(|| format!("not valid {}", encoding.name()))?;
+        return Ok(Decoded {
+            text,
+            original: Original::Encoded {
+                encoding,
+                bom: &bytes[..bom_len],
+            },
+        });
+    }
+    if is_binary(bytes, is_nul_text) {
+        return Err("binary file".to_owned());
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok(Decoded {
+            text: Cow::Borrowed(text),
+            original: Original::Utf8,
+        }),
+        Err(_) if lossy => Ok(Decoded {
+            text: Cow::Owned(to_placeholder_text(bytes)),
+            original: Original::Lossy(bytes),
+        }),
+        Err(_) => Err("not valid UTF-8, use --lossy to sift it anyway".to_owned()),
+    }
+}
+
+impl Decoded<'_> {
+    /// Whether the output is written in another encoding than the input.
+    pub(crate) const fn is_reencoded(&self, output_utf8: bool) -> bool {
+        output_utf8 && matches!(self.original, Original::Encoded { .. })
+    }
+
+    /// Encode the sifted text like the input, or as UTF-8 if `output_utf8` is set.
+    pub(crate) fn encode(&self, sifted: String, output_utf8: bool) -> Vec<u8> {
+        match &self.original {
+            Original::Utf8 => sifted.into_bytes(),
+            Original::Lossy(original) => restore_invalid_bytes(original, &self.text, &sifted),
+            Original::Encoded { .. } if output_utf8 => sifted.into_bytes(),
+            Original::Encoded { encoding, bom } => {
+                let mut out: Vec<u8> = bom.to_vec();
+                if *encoding == UTF_16LE {
+                    out.extend(sifted.encode_utf16().flat_map(u16::to_le_bytes));
+                } else if *encoding == UTF_16BE {
+                    out.extend(sifted.encode_utf16().flat_map(u16::to_be_bytes));
+                } else {
+                    out.extend_from_slice(&encoding.encode(&sifted).0);
+                }
+                out
+            }
+        }
+    }
+}
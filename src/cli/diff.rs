@@ -0,0 +1,63 @@
+use clap::ValueEnum;
+use similar::TextDiff;
+use std::io::IsTerminal;
+
+const RED: &str = "\x1B[31m";
+const GREEN: &str = "\x1B[32m";
+const CYAN: &str = "\x1B[36m";
+const BOLD: &str = "\x1B[1m";
+const RESET: &str = "\x1B[0m";
+
+/// When to color the `--diff` output.
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum ColorChoice {
+    /// Color if stdout is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub(crate) fn is_enabled(self) -> bool {
+        match self {
+            Self::Auto => std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+/// Render a unified diff between an input and its sifted output, or nothing if they are equal.
+pub(crate) fn unified_diff(name: &str, text: &str, sifted: &str, color: bool) -> String {
+    if text == sifted {
+        return String::new();
+    }
+    let diff: String = TextDiff::from_lines(text, sifted)
+        .unified_diff()
+        .header(name, name)
+        .to_string();
+    if !color {
+        return diff;
+    }
+    let mut out: String = String::with_capacity(diff.len());
+    for line in diff.split_inclusive('\n') {
+        let style: &str = if line.starts_with("---") || line.starts_with("+++") {
+            BOLD
+        } else if line.starts_with("@@") {
+            CYAN
+        } else if line.starts_with('-') {
+            RED
+        } else if line.starts_with('+') {
+            GREEN
+        } else {
+            out.push_str(line);
+            continue;
+        };
+        let content: &str = line.strip_suffix('\n').unwrap_or(line);
+        out.push_str(style);
+        out.push_str(content);
+        out.push_str(RESET);
+        out.push_str(&line[content.len()..]);
+    }
+    out
+}
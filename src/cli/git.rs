@@ -0,0 +1,160 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+
+/// Guards the index, as git refuses to update it from several processes at once.
+/// Reading blobs needs no lock, so staged files are still sifted at once.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A regular file staged in the git index.
+pub(crate) struct StagedFile {
+    /// The root of the working tree.
+    root: PathBuf,
+    /// The path relative to `root`, as git prints it.
+    pub(crate) path: String,
+    mode: String,
+    blob: String,
+}
+
+/// Run git within `root`, returning its stdout.
+fn git(root: &Path, args: &[&str], stdin: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input)?;
+    }
+    let output: Output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// The regular files that are added, copied, modified or renamed in the index of the repository around `dir`.
+pub(crate) fn staged_files(dir: &Path) -> io::Result<Vec<StagedFile>> {
+    let toplevel: Vec<u8> = git(dir, &["rev-parse", "--show-toplevel"], None)?;
+    let root: PathBuf = PathBuf::from(String::from_utf8_lossy(&toplevel).trim_end());
+    // Each record is `:<old mode> <new mode> <old blob> <new blob> <status>` and a path, all ended by NUL.
+    let raw: Vec<u8> = git(
+        &root,
+        &[
+            "diff",
+            "--cached",
+            "--raw",
+            "-z",
+            "--no-abbrev",
+            "--no-renames",
+            "--diff-filter=ACMR",
+        ],
+        None,
+    )?;
+    let mut fields = raw.split(|&byte: &u8| byte == 0);
+    let mut files: Vec<StagedFile> = Vec::new();
+    while let (Some(meta), Some(path)) = (fields.next(), fields.next()) {
+        let meta: String = String::from_utf8_lossy(meta).into_owned();
+        let meta: Vec<&str> = meta.split(' ').collect();
+        let (Some(mode), Some(blob)) = (meta.get(1), meta.get(3)) else {
+            continue;
+        };
+        // Skip symlinks and submodules.
+        if !mode.starts_with("100") {
+            continue;
+        }
+        let path: String = String::from_utf8(path.to_vec())
+            .map_err(|_| io::Error::other("a staged path is not valid UTF-8"))?;
+        files.push(StagedFile {
+            root: root.clone(),
+            path,
+            mode: (*mode).to_owned(),
+            blob: (*blob).to_owned(),
+        });
+    }
+    let filtered: Vec<String> = filtered_paths(&root, &files)?;
+    files.retain(|file: &StagedFile| !filtered.contains(&file.path));
+    Ok(files)
+}
+
+/// The paths among `files` with a `filter` attribute, whose blobs are not their contents (e.g. Git LFS pointers).
+fn filtered_paths(root: &Path, files: &[StagedFile]) -> io::Result<Vec<String>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<u8> = Vec::new();
+    for file in files {
+        paths.extend_from_slice(file.path.as_bytes());
+        paths.push(0);
+    }
+    // Each record is a path, the attribute and its value, all ended by NUL.
+    let raw: Vec<u8> = git(
+        root,
+        &["check-attr", "--stdin", "-z", "filter"],
+        Some(&paths),
+    )?;
+    let mut fields = raw.split(|&byte: &u8| byte == 0);
+    let mut filtered: Vec<String> = Vec::new();
+    while let (Some(path), Some(_), Some(value)) = (fields.next(), fields.next(), fields.next()) {
+        if value != b"unspecified" && value != b"unset" {
+            filtered.push(String::from_utf8_lossy(path).into_owned());
+        }
+    }
+    Ok(filtered)
+}
+
+impl StagedFile {
+    /// Read the staged contents, which may differ from the working tree.
+    pub(crate) fn read(&self) -> io::Result<Vec<u8>> {
+        git(&self.root, &["cat-file", "blob", &self.blob], None)
+    }
+
+    /// Replace the staged contents, returning whether the working tree is rewritten too.
+    /// The working tree is only rewritten if it has no unstaged changes to the file, so no unstaged hunk is lost,
+    /// and is checked out from the index so line endings and `text`/`eol` attributes apply as on any checkout.
+    pub(crate) fn stage(&self, contents: &[u8]) -> io::Result<bool> {
+        let _guard = INDEX_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let is_clean: bool = Command::new("git")
+            .args(["diff", "--quiet", "--", &self.path])
+            .current_dir(&self.root)
+            .status()?
+            .success();
+        let blob: Vec<u8> = git(
+            &self.root,
+            &["hash-object", "-w", "--no-filters", "--stdin"],
+            Some(contents),
+        )?;
+        let cache_info: String = format!(
+            "{},{},{}",
+            self.mode,
+            String::from_utf8_lossy(&blob).trim_end(),
+            self.path
+        );
+        git(
+            &self.root,
+            &["update-index", "--cacheinfo", &cache_info],
+            None,
+        )?;
+        if is_clean {
+            git(
+                &self.root,
+                &["checkout-index", "-f", "--", &self.path],
+                None,
+            )?;
+        }
+        Ok(is_clean)
+    }
+}
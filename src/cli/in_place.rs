@@ -0,0 +1,48 @@
+use std::fs::{File, OpenOptions, Permissions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Atomically replace the contents of the file at `path`.
+/// The new contents are written to a temporary file next to it, which is then renamed over it.
+/// The original permissions are kept, and the original file is copied to `path` + `backup_suffix` if given.
+pub(crate) fn write_in_place(
+    path: &Path,
+    contents: &[u8],
+    backup_suffix: Option<&str>,
+) -> io::Result<()> {
+    // Rewrite the target of a symlink instead of replacing the symlink.
+    let path: PathBuf = std::fs::canonicalize(path)?;
+    let permissions: Permissions = std::fs::metadata(&path)?.permissions();
+    let temp_path: PathBuf = sibling_path(&path, ".whitespace-sifter.tmp", true);
+    let result: io::Result<()> = (|| {
+        let mut temp: File = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        temp.write_all(contents)?;
+        temp.sync_all()?;
+        std::fs::set_permissions(&temp_path, permissions)?;
+        if let Some(suffix) = backup_suffix {
+            std::fs::copy(&path, sibling_path(&path, suffix, false))?;
+        }
+        std::fs::rename(&temp_path, &path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// A path in the same directory as `path`, with `suffix` appended to its file name.
+fn sibling_path(path: &Path, suffix: &str, is_hidden: bool) -> PathBuf {
+    let mut file_name = std::ffi::OsString::new();
+    if is_hidden {
+        file_name.push(".");
+    }
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(suffix);
+    if is_hidden {
+        file_name.push(format!(".{}", std::process::id()));
+    }
+    path.with_file_name(file_name)
+}
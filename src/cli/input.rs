@@ -0,0 +1,73 @@
+use crate::cli::git::StagedFile;
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+/// Files at least this large are memory-mapped instead of read.
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// Something for the binary to sift.
+pub(crate) enum Input {
+    Text(String),
+    Stdin,
+    File(PathBuf),
+    Staged(StagedFile),
+}
+
+/// The bytes of an input.
+pub(crate) enum Contents {
+    Read(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Read(bytes) => bytes,
+            Self::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+impl Input {
+    /// A name for the input in messages.
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Self::Text(_) => "<text>".to_owned(),
+            Self::Stdin => "<stdin>".to_owned(),
+            Self::File(path) => path.display().to_string(),
+            Self::Staged(file) => file.path.clone(),
+        }
+    }
+
+    /// Read the input, memory-mapping a large file if `map` is set.
+    /// Files being written by other processes, such as the ones `--watch` sees change, must not be mapped.
+    pub(crate) fn read(&self, map: bool) -> std::io::Result<Contents> {
+        match self {
+            Self::Text(text) => Ok(Contents::Read(text.clone().into_bytes())),
+            Self::Stdin => {
+                let mut buf: Vec<u8> = Vec::new();
+                std::io::stdin().read_to_end(&mut buf)?;
+                Ok(Contents::Read(buf))
+            }
+            Self::File(path) => {
+                let mut file: File = File::open(path)?;
+                let metadata: std::fs::Metadata = file.metadata()?;
+                if map && metadata.is_file() && metadata.len() >= MMAP_THRESHOLD {
+                    // SAFETY: The mapping is decoded and borrowed as `&str`, so another process writing the file meanwhile is undefined behaviour,
+                    // which is why callers only map files nothing else is expected to write.
+                    // Rewrites in place replace the file instead of writing to it, so they never change the mapping.
+                    return Ok(Contents::Mapped(unsafe { Mmap::map(&file)? }));
+                }
+                let mut buf: Vec<u8> = Vec::new();
+                file.read_to_end(&mut buf)?;
+                Ok(Contents::Read(buf))
+            }
+            Self::Staged(file) => file.read().map(Contents::Read),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+use whitespace_sifter::WhitespaceSifter;
+
+/// An inclusive range of 1-based line numbers, as given to `--lines`.
+#[derive(Clone, Copy)]
+pub(crate) struct LineRange {
+    start: usize,
+    end: usize,
+}
+
+impl LineRange {
+    /// Lines `start` to `end`, where both are 1-based and inclusive.
+    pub(crate) const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// Parse `START:END`, where both are 1-based and inclusive.
+pub(crate) fn parse_line_range(arg: &str) -> Result<LineRange, String> {
+    let (start, end): (&str, &str) = arg
+        .split_once(':')
+        .ok_or_else(|| format!("expected `START:END`, got `{arg}`"))?;
+    let parse = |line: &str| -> Result<usize, String> {
+        match line.parse::<usize>() {
+            Ok(0) => Err("line numbers start at 1".to_owned()),
+            Ok(line) => Ok(line),
+            Err(err) => Err(format!("invalid line number `{line}`: {err}")),
+        }
+    };
+    let (start, end): (usize, usize) = (parse(start)?, parse(end)?);
+    if start > end {
+        return Err(format!("the range `{arg}` ends before it starts"));
+    }
+    Ok(LineRange::new(start, end))
+}
+
+/// The byte ranges of the given lines of `text`, sorted and with overlapping or adjacent ranges merged.
+/// Each byte range ends before the line ending of its last line, so that line ending stays untouched.
+pub(crate) fn line_byte_ranges(text: &str, lines: &[LineRange]) -> Vec<Range<usize>> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(ind, _)| ind + 1))
+        .collect();
+    // A trailing line ending does not start another line.
+    let line_count: usize = line_starts.len() - usize::from(text.ends_with('\n'));
+    let mut lines: Vec<LineRange> = lines.to_vec();
+    lines.sort_unstable_by_key(|lines: &LineRange| lines.start);
+    let mut merged: Vec<LineRange> = Vec::with_capacity(lines.len());
+    for range in lines {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = last.end.max(range.end)
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+        .into_iter()
+        .filter(|range: &LineRange| range.start <= line_count)
+        .map(|range: LineRange| {
+            let end_line: usize = range.end.min(line_count);
+            let end: usize = line_starts
+                .get(end_line)
+                .map_or(text.len(), |&next_start: &usize| {
+                    let line: &str = &text[..next_start - 1];
+                    line.strip_suffix('\r').unwrap_or(line).len()
+                });
+            line_starts[range.start - 1]..end
+        })
+        .collect()
+}
+
+/// Sift only the given lines of `text`, preserving newlines.
+pub(crate) fn sift_lines(text: &str, lines: &[LineRange]) -> String {
+    let mut out: String = String::with_capacity(text.len());
+    let mut ind: usize = 0;
+    for range in line_byte_ranges(text, lines) {
+        out.push_str(&text[ind..range.start]);
+        let segment: &str = &text[range.clone()];
+        out.push_str(&segment.sift_preserve_newlines());
+        ind = range.end;
+    }
+    out.push_str(&text[ind..]);
+    out
+}
+
+/// Find the first whitespaces within the given lines of `text` that [`sift_lines`] would change.
+pub(crate) fn find_unsifted_lines(text: &str, lines: &[LineRange]) -> Option<Range<usize>> {
+    line_byte_ranges(text, lines)
+        .into_iter()
+        .find_map(|range: Range<usize>| {
+            let segment: &str = &text[range.clone()];
+            segment
+                .find_unsifted_preserve_newlines()
+                .map(|unsifted: Range<usize>| {
+                    range.start + unsifted.start..range.start + unsifted.end
+                })
+        })
+}
+
+/// The byte ranges that [`sift_lines`] edits.
+pub(crate) fn sift_edits_lines(text: &str, lines: &[LineRange]) -> Vec<Range<usize>> {
+    line_byte_ranges(text, lines)
+        .into_iter()
+        .flat_map(|range: Range<usize>| {
+            let segment: &str = &text[range.clone()];
+            segment
+                .sift_edits_preserve_newlines()
+                .into_iter()
+                .map(|(edit, _): (Range<usize>, &str)| {
+                    range.start + edit.start..range.start + edit.end
+                })
+                .collect::<Vec<Range<usize>>>()
+        })
+        .collect()
+}
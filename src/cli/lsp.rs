@@ -0,0 +1,248 @@
+use crate::cli::lines::{line_byte_ranges, LineRange};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{Formatting, RangeFormatting, Request as RequestTrait};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, OneOf, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use whitespace_sifter::WhitespaceSifter;
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A language server that formats documents by sifting them and reports their unsifted whitespaces.
+/// Positions are counted in UTF-16 code units, the encoding every client supports.
+/// Newlines are always preserved, as an editor would otherwise join the whole document into one line.
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: HashMap<Uri, String>,
+}
+
+/// Serve the Language Server Protocol over `connection` until the client shuts the server down.
+/// Malformed messages are answered or logged, so only the transport failing ends the server early.
+pub(crate) fn serve(connection: &Connection) -> LspResult<()> {
+    let capabilities: ServerCapabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    let mut server: Server = Server {
+        connection,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.handle_request(request)?;
+            }
+            Message::Notification(notification) => server.handle_notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+impl Server<'_> {
+    fn handle_request(&self, request: Request) -> LspResult<()> {
+        let id: RequestId = request.id.clone();
+        let response: Response = match request.method.as_str() {
+            Formatting::METHOD => match request_params::<Formatting>(request) {
+                Ok(params) => Response::new_ok(id, self.format(&params.text_document.uri, None)),
+                Err(response) => response,
+            },
+            RangeFormatting::METHOD => match request_params::<RangeFormatting>(request) {
+                Ok(params) => Response::new_ok(
+                    id,
+                    self.format(&params.text_document.uri, Some(params.range)),
+                ),
+                Err(response) => response,
+            },
+            method => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{method}`"),
+            ),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> LspResult<()> {
+        let uri: Uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params): Option<DidOpenTextDocumentParams> =
+                    notification_params::<DidOpenTextDocument>(notification)
+                else {
+                    return Ok(());
+                };
+                let uri: Uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params): Option<DidChangeTextDocumentParams> =
+                    notification_params::<DidChangeTextDocument>(notification)
+                else {
+                    return Ok(());
+                };
+                let uri: Uri = params.text_document.uri;
+                // With full sync, the last change holds the whole document.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params): Option<DidCloseTextDocumentParams> =
+                    notification_params::<DidCloseTextDocument>(notification)
+                else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                params.text_document.uri
+            }
+            _ => return Ok(()),
+        };
+        let diagnostics: Vec<Diagnostic> = self
+            .documents
+            .get(&uri)
+            .map(|text: &String| diagnostics(text))
+            .unwrap_or_default();
+        let params: PublishDiagnosticsParams =
+            PublishDiagnosticsParams::new(uri, diagnostics, None);
+        self.connection
+            .sender
+            .send(Notification::new(PublishDiagnostics::METHOD.to_owned(), params).into())?;
+        Ok(())
+    }
+
+    /// The minimal edits that sift a document, or only the lines touched by `range`, preserving newlines.
+    fn format(&self, uri: &Uri, range: Option<Range>) -> Option<Vec<TextEdit>> {
+        let text: &str = self.documents.get(uri)?;
+        let (start, end): (usize, usize) = match range {
+            None => (0, text.len()),
+            Some(range) => {
+                // A range ending at the start of a line does not touch that line.
+                let end_line: u32 = if range.end.character == 0 && range.end.line > range.start.line
+                {
+                    range.end.line - 1
+                } else {
+                    range.end.line
+                };
+                let lines: LineRange =
+                    LineRange::new(range.start.line as usize + 1, end_line as usize + 1);
+                let byte_range: std::ops::Range<usize> =
+                    line_byte_ranges(text, &[lines]).into_iter().next()?;
+                (byte_range.start, byte_range.end)
+            }
+        };
+        let segment: &str = &text[start..end];
+        let edits: Vec<(std::ops::Range<usize>, &str)> = segment.sift_edits_preserve_newlines();
+        let mut positions: Positions = Positions::new(text);
+        Some(
+            edits
+                .into_iter()
+                .map(|(edit, replacement): (std::ops::Range<usize>, &str)| {
+                    let edit_start: Position = positions.position(start + edit.start);
+                    let edit_end: Position = positions.position(start + edit.end);
+                    TextEdit::new(Range::new(edit_start, edit_end), replacement.to_owned())
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The params of a request, or an `InvalidParams` error to reply with if they are malformed.
+fn request_params<R: RequestTrait>(request: Request) -> Result<R::Params, Response> {
+    let id: RequestId = request.id.clone();
+    request
+        .extract(R::METHOD)
+        .map(|(_, params): (RequestId, R::Params)| params)
+        .map_err(|err| Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()))
+}
+
+/// The params of a notification, or `None` if they are malformed.
+/// Notifications get no reply, so a malformed one is only logged and the server keeps serving.
+fn notification_params<N: NotificationTrait>(notification: Notification) -> Option<N::Params> {
+    notification
+        .extract(N::METHOD)
+        .map_err(|err| eprintln!("Ignoring a malformed `{}` notification: {err}", N::METHOD))
+        .ok()
+}
+
+/// A diagnostic for every run of whitespaces that sifting, preserving newlines, would change.
+fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut positions: Positions = Positions::new(text);
+    let mut offset: usize = 0;
+    loop {
+        let rest: &str = &text[offset..];
+        let Some(unsifted) = rest.find_unsifted_preserve_newlines() else {
+            return diagnostics;
+        };
+        let (start, end): (usize, usize) = (offset + unsifted.start, offset + unsifted.end);
+        let message: &str = if start == 0 {
+            "Leading whitespace"
+        } else if end == text.len() {
+            "Trailing whitespace"
+        } else {
+            "Duplicate whitespace"
+        };
+        let (start_position, end_position): (Position, Position) =
+            (positions.position(start), positions.position(end));
+        diagnostics.push(Diagnostic {
+            range: Range::new(start_position, end_position),
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            source: Some("whitespace-sifter".to_owned()),
+            message: message.to_owned(),
+            ..Diagnostic::default()
+        });
+        // Runs end before content, so the rest never starts with a whitespace that only looks leading.
+        offset = end;
+    }
+}
+
+/// Converts ascending byte offsets of a text to positions, in one pass over the text.
+/// The character is counted in UTF-16 code units.
+struct Positions<'a> {
+    text: &'a str,
+    offset: usize,
+    position: Position,
+}
+
+impl<'a> Positions<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            position: Position::new(0, 0),
+        }
+    }
+
+    /// The position of byte `offset`, which may not be before the offset given last.
+    #[allow(clippy::cast_possible_truncation)]
+    fn position(&mut self, offset: usize) -> Position {
+        for ch in self.text[self.offset..offset].chars() {
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.character = 0;
+            } else {
+                self.position.character += ch.len_utf16() as u32;
+            }
+        }
+        self.offset = offset;
+        self.position
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+
+/// Run `work` on every item on up to `jobs` threads, passing the results to `report` in the order of the items.
+/// Each result is reported as soon as it and every result before it are ready.
+pub(crate) fn run_ordered<T: Sync, R: Send>(
+    items: &[T],
+    jobs: NonZeroUsize,
+    work: impl Fn(&T) -> R + Sync,
+    mut report: impl FnMut(&T, R),
+) {
+    let next: AtomicUsize = AtomicUsize::new(0);
+    let (sender, receiver) = channel::<(usize, R)>();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.get().min(items.len()) {
+            let sender: Sender<(usize, R)> = sender.clone();
+            let (next, work): (&AtomicUsize, &(dyn Fn(&T) -> R + Sync)) = (&next, &work);
+            scope.spawn(move || loop {
+                let ind: usize = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(ind) else {
+                    return;
+                };
+                if sender.send((ind, work(item))).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(sender);
+        let mut pending: BTreeMap<usize, R> = BTreeMap::new();
+        let mut next_report: usize = 0;
+        for (ind, result) in receiver {
+            pending.insert(ind, result);
+            while let Some(result) = pending.remove(&next_report) {
+                report(&items[next_report], result);
+                next_report += 1;
+            }
+        }
+    });
+}
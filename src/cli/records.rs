@@ -0,0 +1,56 @@
+use std::ops::Range;
+
+/// Parse a record separator, which may not be empty.
+pub(crate) fn parse_separator(separator: &str) -> Result<String, String> {
+    if separator.is_empty() {
+        return Err("the separator may not be empty".to_owned());
+    }
+    Ok(separator.to_owned())
+}
+
+/// Sift every record of `text` on its own, keeping the separators between them as they are.
+pub(crate) fn sift_records(text: &str, separator: &str, sift: impl Fn(&str) -> String) -> String {
+    let mut out: String = String::with_capacity(text.len());
+    for (ind, record) in text.split(separator).enumerate() {
+        if ind > 0 {
+            out.push_str(separator);
+        }
+        out.push_str(&sift(record));
+    }
+    out
+}
+
+/// Find the first whitespaces that [`sift_records`] would change.
+pub(crate) fn find_unsifted_records(
+    text: &str,
+    separator: &str,
+    find_unsifted: impl Fn(&str) -> Option<Range<usize>>,
+) -> Option<Range<usize>> {
+    let mut start: usize = 0;
+    for record in text.split(separator) {
+        if let Some(unsifted) = find_unsifted(record) {
+            return Some(start + unsifted.start..start + unsifted.end);
+        }
+        start += record.len() + separator.len();
+    }
+    None
+}
+
+/// The byte ranges that [`sift_records`] edits, given those that sifting a single record edits.
+pub(crate) fn sift_edits_records(
+    text: &str,
+    separator: &str,
+    sift_edits: impl Fn(&str) -> Vec<Range<usize>>,
+) -> Vec<Range<usize>> {
+    let mut edits: Vec<Range<usize>> = Vec::new();
+    let mut start: usize = 0;
+    for record in text.split(separator) {
+        edits.extend(
+            sift_edits(record)
+                .into_iter()
+                .map(|edit: Range<usize>| start + edit.start..start + edit.end),
+        );
+        start += record.len() + separator.len();
+    }
+    edits
+}
@@ -0,0 +1,192 @@
+use clap::ValueEnum;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The format of the `--stats` report.
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum StatsFormat {
+    Text,
+    Json,
+}
+
+/// How many of each line ending there are.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct LineEndings {
+    lf: usize,
+    crlf: usize,
+    cr: usize,
+}
+
+impl LineEndings {
+    fn count(text: &str) -> Self {
+        let bytes: &[u8] = text.as_bytes();
+        let mut line_endings: Self = Self::default();
+        for (ind, byte) in bytes.iter().enumerate() {
+            match byte {
+                b'\n' if ind > 0 && bytes[ind - 1] == b'\r' => line_endings.crlf += 1,
+                b'\n' => line_endings.lf += 1,
+                b'\r' if bytes.get(ind + 1) != Some(&b'\n') => line_endings.cr += 1,
+                _ => {}
+            }
+        }
+        line_endings
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.lf += other.lf;
+        self.crlf += other.crlf;
+        self.cr += other.cr;
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"lf\":{},\"crlf\":{},\"cr\":{}}}",
+            self.lf, self.crlf, self.cr
+        )
+    }
+}
+
+/// What sifting did to an input.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Stats {
+    pub(crate) bytes_in: usize,
+    pub(crate) bytes_out: usize,
+    runs_collapsed: usize,
+    lines_trimmed: usize,
+    line_endings_in: LineEndings,
+    line_endings_out: LineEndings,
+}
+
+impl Stats {
+    /// Count what sifting the decoded text of an input into `sifted` did, given the byte ranges it edited.
+    /// The byte counts are left for the caller, as they depend on the encoding.
+    pub(crate) fn new(text: &str, edits: &[Range<usize>], sifted: &str) -> Self {
+        Self {
+            bytes_in: 0,
+            bytes_out: 0,
+            runs_collapsed: edits.len(),
+            lines_trimmed: count_trimmed_lines(text, edits),
+            line_endings_in: LineEndings::count(text),
+            line_endings_out: LineEndings::count(sifted),
+        }
+    }
+
+    pub(crate) fn add(&mut self, other: &Self) {
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+        self.runs_collapsed += other.runs_collapsed;
+        self.lines_trimmed += other.lines_trimmed;
+        self.line_endings_in.add(&other.line_endings_in);
+        self.line_endings_out.add(&other.line_endings_out);
+    }
+
+    fn to_text(self) -> String {
+        format!(
+            "{} -> {} bytes, {} runs collapsed, {} lines trimmed, line endings (LF/CR-LF/CR) {}/{}/{} -> {}/{}/{}",
+            self.bytes_in,
+            self.bytes_out,
+            self.runs_collapsed,
+            self.lines_trimmed,
+            self.line_endings_in.lf,
+            self.line_endings_in.crlf,
+            self.line_endings_in.cr,
+            self.line_endings_out.lf,
+            self.line_endings_out.crlf,
+            self.line_endings_out.cr,
+        )
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "\"bytes_in\":{},\"bytes_out\":{},\"runs_collapsed\":{},\"lines_trimmed\":{},\"line_endings_in\":{},\"line_endings_out\":{}",
+            self.bytes_in,
+            self.bytes_out,
+            self.runs_collapsed,
+            self.lines_trimmed,
+            self.line_endings_in.to_json(),
+            self.line_endings_out.to_json(),
+        )
+    }
+}
+
+/// Count the lines whose leading or trailing whitespaces the sorted `edits` change.
+fn count_trimmed_lines(text: &str, edits: &[Range<usize>]) -> usize {
+    let is_edited = |range: Range<usize>| -> bool {
+        let ind: usize = edits.partition_point(|edit: &Range<usize>| edit.end <= range.start);
+        !range.is_empty()
+            && edits
+                .get(ind)
+                .is_some_and(|edit: &Range<usize>| edit.start < range.end)
+    };
+    let mut count: usize = 0;
+    let mut start: usize = 0;
+    for line in text.split('\n') {
+        let content: &str = line.strip_suffix('\r').unwrap_or(line);
+        let leading_end: usize = start + content.len()
+            - content
+                .trim_start_matches(|ch: char| ch.is_ascii_whitespace())
+                .len();
+        let trailing_start: usize = start
+            + content
+                .trim_end_matches(|ch: char| ch.is_ascii_whitespace())
+                .len();
+        if is_edited(start..leading_end) || is_edited(trailing_start..start + content.len()) {
+            count += 1;
+        }
+        start += line.len() + 1;
+    }
+    count
+}
+
+/// Report the stats of every input and their total.
+pub(crate) fn report(format: StatsFormat, inputs: &[(String, Stats)]) -> String {
+    let mut total: Stats = Stats::default();
+    for (_, stats) in inputs {
+        total.add(stats);
+    }
+    let mut out: String = String::new();
+    match format {
+        StatsFormat::Text => {
+            for (name, stats) in inputs {
+                let _ = writeln!(out, "{name}: {}", stats.to_text());
+            }
+            let _ = writeln!(out, "total: {}", total.to_text());
+        }
+        StatsFormat::Json => {
+            out.push_str("{\"inputs\":[");
+            for (ind, (name, stats)) in inputs.iter().enumerate() {
+                if ind > 0 {
+                    out.push(',');
+                }
+                let _ = write!(
+                    out,
+                    "{{\"name\":{},{}}}",
+                    json_string(name),
+                    stats.to_json()
+                );
+            }
+            let _ = writeln!(out, "],\"total\":{{{}}}}}", total.to_json());
+        }
+    }
+    out
+}
+
+fn json_string(text: &str) -> String {
+    let mut out: String = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0'..='\x1F' => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
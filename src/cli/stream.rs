@@ -0,0 +1,60 @@
+use crate::cli::binary::{restore_invalid_bytes, to_placeholder_text};
+use std::io::{self, BufRead, Write};
+use whitespace_sifter::WhitespaceSifter;
+
+/// Sift `reader` line by line, writing and flushing each line as soon as it is complete.
+/// Only one line is held in memory at a time.
+///
+/// Lines are sifted like a whole input would be, except that a kept whitespace is written along with the line before it.
+/// So the line ending of the last line is kept, or the whitespace kept in its place without `preserve_newlines`.
+pub(crate) fn stream(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    preserve_newlines: bool,
+    lossy: bool,
+) -> io::Result<()> {
+    let mut line: Vec<u8> = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        let content_len: usize = if line.ends_with(b"\r\n") {
+            line.len() - 2
+        } else if line.ends_with(b"\n") {
+            line.len() - 1
+        } else {
+            line.len()
+        };
+        let (text, is_lossy): (String, bool) = match std::str::from_utf8(&line) {
+            Ok(text) => (text.to_owned(), false),
+            Err(_) if lossy => (to_placeholder_text(&line), true),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "not valid UTF-8, use --lossy to sift it anyway",
+                ))
+            }
+        };
+        let (content, line_ending): (&str, &str) = text.split_at(content_len);
+        let sifted: String = content.sift();
+        // A line of whitespaces joins the run of whitespaces around it, whose kept whitespace is already written.
+        if sifted.is_empty() {
+            continue;
+        }
+        let trimmed_len: usize = content
+            .trim_end_matches(|ch: char| ch.is_ascii_whitespace())
+            .len();
+        let kept: &str = match content[trimmed_len..].chars().next() {
+            Some(ch) if !preserve_newlines => &content[trimmed_len..trimmed_len + ch.len_utf8()],
+            _ => line_ending,
+        };
+        let out: String = sifted + kept;
+        if is_lossy {
+            writer.write_all(&restore_invalid_bytes(&line, &text, &out))?;
+        } else {
+            writer.write_all(out.as_bytes())?;
+        }
+        writer.flush()?;
+    }
+}
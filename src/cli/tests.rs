@@ -0,0 +1,677 @@
+use crate::cli::binary::{is_binary, restore_invalid_bytes, to_placeholder_text};
+use crate::cli::check::line_column;
+use crate::cli::decode::{decode, parse_encoding, Decoded, EncodingArg};
+use crate::cli::diff::unified_diff;
+use crate::cli::git::{staged_files, StagedFile};
+use crate::cli::in_place::write_in_place;
+use crate::cli::input::{Contents, Input};
+use crate::cli::lines::{find_unsifted_lines, parse_line_range, sift_lines, LineRange};
+use crate::cli::lsp::serve;
+use crate::cli::pool::run_ordered;
+use crate::cli::records::{find_unsifted_records, sift_records};
+use crate::cli::stats::{report, Stats, StatsFormat};
+use crate::cli::stream::stream;
+use crate::cli::test_inputs::all_strings;
+use crate::cli::walk::{backup_glob, glob_set, parse_glob, walk, WalkOptions};
+use crate::cli::watch::{next_changes, watch, Fingerprints};
+use crate::Args;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+use globset::Glob;
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use serde_json::{json, Value};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use whitespace_sifter::WhitespaceSifter;
+
+#[test]
+fn test_line_column() {
+    assert_eq!(line_column("", 0), (1, 1));
+    assert_eq!(line_column("ab  c", 2), (1, 3));
+    assert_eq!(line_column("a\r\n❤️b  c", 10), (2, 4));
+}
+
+#[test]
+fn test_binary_detection() {
+    assert!(is_binary(b"text\0with a NUL", false));
+    assert!(!is_binary(b"text\0with a NUL", true));
+    assert!(is_binary(&[0xFF, 0xFE, b'a', 0x80], false));
+    assert!(!is_binary(b"plain  text", false));
+    assert!(!is_binary(b"caf\xC3\xA9 or \xE9t\xE9 in Latin-1", false));
+}
+
+#[test]
+fn test_lossy_sifting() {
+    let original: &[u8] = b"  a\xFF  \xC3\n\n b\xE9  ";
+    let text: String = to_placeholder_text(original);
+    assert_eq!(text.len(), original.len());
+    assert_eq!(
+        restore_invalid_bytes(original, &text, &text.sift_preserve_newlines()),
+        b"a\xFF \xC3\nb\xE9"
+    );
+}
+
+#[test]
+fn test_encoding_round_trip() {
+    let mut bytes: Vec<u8> = vec![0xFE, 0xFF];
+    bytes.extend("a  ❤️  b".encode_utf16().flat_map(u16::to_be_bytes));
+    let decoded: Decoded = decode(&bytes, Some(EncodingArg::Auto), false, false).unwrap();
+    assert_eq!(decoded.text, "a  ❤️  b");
+    let mut out: Vec<u8> = vec![0xFE, 0xFF];
+    out.extend("a ❤️ b".encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(decoded.encode(decoded.text.sift(), false), out);
+    assert_eq!(
+        decoded.encode(decoded.text.sift(), true),
+        "a ❤️ b".as_bytes()
+    );
+
+    let windows_1252: EncodingArg = parse_encoding("windows-1252").unwrap();
+    let decoded: Decoded =
+        decode(b"caf\xE9  \x93ok\x94", Some(windows_1252), false, false).unwrap();
+    assert_eq!(decoded.text, "café  “ok”");
+    assert_eq!(
+        decoded.encode(decoded.text.sift(), false),
+        b"caf\xE9 \x93ok\x94"
+    );
+
+    // Undecodable bytes in other encodings are never replaced, even with `--lossy`.
+    let shift_jis: EncodingArg = parse_encoding("shift_jis").unwrap();
+    let bytes: &[u8] = b"a  \x82\xA0  b";
+    let decoded: Decoded = decode(bytes, Some(shift_jis), true, false).unwrap();
+    assert_eq!(decoded.encode(decoded.text.sift(), false), b"a \x82\xA0 b");
+    assert!(decode(b"a  \x82\xA0  b \xFF\xFE c", Some(shift_jis), true, false).is_err());
+    let utf_16le: EncodingArg = parse_encoding("utf-16le").unwrap();
+    assert!(decode(b"a\0 \0\x00\xD8b\0", Some(utf_16le), true, false).is_err());
+}
+
+#[test]
+fn test_stats_report() {
+    let text: &str = " a  b \r\n\r\n c\t\rd\n";
+    let mut stats: Stats = Stats::new(
+        text,
+        &text
+            .sift_edits_preserve_newlines()
+            .into_iter()
+            .map(|(edit, _)| edit)
+            .collect::<Vec<_>>(),
+        &text.sift_preserve_newlines(),
+    );
+    stats.bytes_in = text.len();
+    assert_eq!(
+        report(StatsFormat::Json, &[("a\"b.txt".to_owned(), stats)]),
+        "{\"inputs\":[{\"name\":\"a\\\"b.txt\",\"bytes_in\":16,\"bytes_out\":0,\"runs_collapsed\":5,\"lines_trimmed\":2,\"line_endings_in\":{\"lf\":1,\"crlf\":2,\"cr\":1},\"line_endings_out\":{\"lf\":0,\"crlf\":1,\"cr\":0}}],\"total\":{\"bytes_in\":16,\"bytes_out\":0,\"runs_collapsed\":5,\"lines_trimmed\":2,\"line_endings_in\":{\"lf\":1,\"crlf\":2,\"cr\":1},\"line_endings_out\":{\"lf\":0,\"crlf\":1,\"cr\":0}}}\n"
+    );
+}
+
+#[test]
+fn test_stats_follow_the_sifting_mode() {
+    let stats = |args: &[&str], text: &str| -> String {
+        let args: Args =
+            Args::try_parse_from(std::iter::once("whitespace-sifter").chain(args.iter().copied()))
+                .unwrap();
+        let stats: Stats = Stats::new(text, &args.sift_edits(text), &args.sift(text));
+        report(StatsFormat::Text, &[("x".to_owned(), stats)])
+    };
+    assert!(stats(&["--lines", "1:1"], "a  b\nc  d  e  f")
+        .starts_with("x: 0 -> 0 bytes, 1 runs collapsed, 0 lines trimmed,"));
+    assert!(stats(&["-z"], " a \0 b \0")
+        .starts_with("x: 0 -> 0 bytes, 4 runs collapsed, 1 lines trimmed,"));
+}
+
+#[test]
+fn test_unified_diff() {
+    let text: &str = "a  b\n  c\nd\n";
+    assert_eq!(unified_diff("x.txt", text, text, true), "");
+    assert_eq!(
+        unified_diff("x.txt", text, &text.sift_preserve_newlines(), false),
+        "--- x.txt\n+++ x.txt\n@@ -1,3 +1,3 @@\n-a  b\n-  c\n-d\n+a b\n+c\n+d\n\\ No newline at end of file\n"
+    );
+    assert_eq!(
+        unified_diff("x.txt", "a \n", "a", true),
+        "\x1B[1m--- x.txt\x1B[0m\n\x1B[1m+++ x.txt\x1B[0m\n\x1B[36m@@ -1 +1 @@\x1B[0m\n\x1B[31m-a \x1B[0m\n\x1B[32m+a\x1B[0m\n\\ No newline at end of file\n"
+    );
+}
+
+#[test]
+fn test_sift_lines() {
+    assert!(parse_line_range("0:2").is_err());
+    assert!(parse_line_range("3:2").is_err());
+    assert!(parse_line_range("3").is_err());
+    let text: &str = "  a  b  \r\n  c  \n\n\n  d  \ne  f\n";
+    let lines = |ranges: &[&str]| -> Vec<LineRange> {
+        ranges
+            .iter()
+            .map(|range: &&str| parse_line_range(range).unwrap())
+            .collect()
+    };
+    assert_eq!(
+        sift_lines(text, &lines(&["2:4"])),
+        "  a  b  \r\nc\n  d  \ne  f\n"
+    );
+    assert_eq!(
+        sift_lines(text, &lines(&["6:9", "2:2", "1:1"])),
+        "a b\r\nc\n\n\n  d  \ne f\n"
+    );
+    assert_eq!(sift_lines(text, &lines(&["7:9"])), text);
+    assert_eq!(
+        sift_lines(text, &lines(&["1:18446744073709551615", "2:3"])),
+        sift_lines(text, &lines(&["1:6"]))
+    );
+    assert_eq!(find_unsifted_lines(text, &lines(&["2:2"])), Some(10..12));
+    assert_eq!(find_unsifted_lines(text, &lines(&["3:4"])), Some(16..17));
+    let sifted: String = sift_lines(text, &lines(&["2:4"]));
+    assert_eq!(find_unsifted_lines(&sifted, &lines(&["2:2"])), None);
+}
+
+#[test]
+fn test_completions_and_man() {
+    Args::command().debug_assert();
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+        let mut out: Vec<u8> = Vec::new();
+        clap_complete::generate(shell, &mut Args::command(), "whitespace-sifter", &mut out);
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("preserve-newlines"));
+    }
+    let mut out: Vec<u8> = Vec::new();
+    clap_mangen::Man::new(Args::command())
+        .render(&mut out)
+        .unwrap();
+    assert!(String::from_utf8(out).unwrap().starts_with(".ie"));
+}
+
+#[test]
+fn test_sift_records() {
+    let text: &str = " a  b \0 c\n\n d \0\0";
+    let sift = |record: &str| record.sift_preserve_newlines();
+    assert_eq!(sift_records(text, "\0", sift), "a b\0c\nd\0\0");
+    assert_eq!(
+        sift_records("x  y ;; z;;", ";;", |record: &str| record.sift()),
+        "x y;;z;;"
+    );
+    let find_unsifted = |record: &str| record.find_unsifted_preserve_newlines();
+    assert_eq!(
+        find_unsifted_records("a\0b\0c  d", "\0", find_unsifted),
+        Some(5..7)
+    );
+    assert_eq!(find_unsifted_records("a\0b\0\0", "\0", find_unsifted), None);
+}
+
+#[test]
+fn test_stream() {
+    let sift_stream = |input: &[u8], preserve_newlines: bool, lossy: bool| -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        stream(input, &mut out, preserve_newlines, lossy).unwrap();
+        out
+    };
+    assert_eq!(
+        sift_stream(b"\n  a  b \r\n\r\n c\n", true, false),
+        b"a b\r\nc\n"
+    );
+    assert_eq!(sift_stream(b"a \n\nb\n", false, false), b"a b\n");
+    assert_eq!(sift_stream(b"a\xFF  b\n", true, true), b"a\xFF b\n");
+    assert!(stream(&b"a\xFF\n"[..], &mut Vec::new(), true, false).is_err());
+
+    // Whenever the input ends with content and has no lone CR, streaming sifts exactly like sifting the whole input.
+    for input in all_strings(&["a", " ", "\t", "\r\n", "\n", "❤"], 5) {
+        let input: String = input + "a";
+        assert_eq!(
+            sift_stream(input.as_bytes(), false, false),
+            input.sift().as_bytes(),
+            "{input:?}"
+        );
+        assert_eq!(
+            sift_stream(input.as_bytes(), true, false),
+            input.sift_preserve_newlines().as_bytes(),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_run_ordered() {
+    let items: Vec<u64> = (0..32).collect();
+    let mut reported: Vec<u64> = Vec::new();
+    run_ordered(
+        &items,
+        NonZeroUsize::new(4).unwrap(),
+        |item: &u64| {
+            std::thread::sleep(std::time::Duration::from_millis(item % 3));
+            item * 2
+        },
+        |item: &u64, result: u64| {
+            assert_eq!(result, item * 2);
+            reported.push(*item);
+        },
+    );
+    assert_eq!(reported, items);
+}
+
+#[test]
+fn test_lsp() {
+    let (server, client) = Connection::memory();
+    let server = std::thread::spawn(move || serve(&server).unwrap());
+    let request = |id: i32, method: &str, params: Value| -> Value {
+        let request: Request = Request::new(RequestId::from(id), method.to_owned(), params);
+        client.sender.send(request.into()).unwrap();
+        match client.receiver.recv().unwrap() {
+            Message::Response(Response { result, .. }) => result.unwrap(),
+            message => panic!("unexpected {message:?}"),
+        }
+    };
+    let notify = |method: &str, params: Value| {
+        let notification: Notification = Notification::new(method.to_owned(), params);
+        client.sender.send(notification.into()).unwrap();
+    };
+    request(1, "initialize", json!({ "capabilities": {} }));
+    notify("initialized", json!({}));
+
+    let document: Value = json!({ "uri": "file:///a.txt" });
+    notify(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": { "uri": "file:///a.txt", "languageId": "text", "version": 1, "text": "❤  a\n\n\nb \n" }
+        }),
+    );
+    let Message::Notification(diagnostics) = client.receiver.recv().unwrap() else {
+        panic!("expected diagnostics");
+    };
+    let ranges: Vec<Value> = diagnostics.params["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|diagnostic: &Value| diagnostic["range"].clone())
+        .collect();
+    assert_eq!(
+        ranges,
+        [
+            json!({ "start": { "line": 0, "character": 1 }, "end": { "line": 0, "character": 3 } }),
+            json!({ "start": { "line": 0, "character": 4 }, "end": { "line": 3, "character": 0 } }),
+            json!({ "start": { "line": 3, "character": 1 }, "end": { "line": 4, "character": 0 } }),
+        ]
+    );
+
+    let options: Value = json!({ "tabSize": 4, "insertSpaces": true });
+    assert_eq!(
+        request(
+            2,
+            "textDocument/formatting",
+            json!({ "textDocument": document, "options": options })
+        ),
+        json!([
+            { "range": { "start": { "line": 0, "character": 2 }, "end": { "line": 0, "character": 3 } }, "newText": "" },
+            { "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 3, "character": 0 } }, "newText": "" },
+            { "range": { "start": { "line": 3, "character": 1 }, "end": { "line": 4, "character": 0 } }, "newText": "" },
+        ])
+    );
+    assert_eq!(
+        request(
+            3,
+            "textDocument/rangeFormatting",
+            json!({
+                "textDocument": document,
+                "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 3, "character": 0 } },
+                "options": options
+            })
+        ),
+        json!([{
+            "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 2, "character": 0 } },
+            "newText": ""
+        }])
+    );
+
+    // Malformed messages neither get a result nor stop the server.
+    notify("textDocument/didOpen", json!({ "textDocument": {} }));
+    let malformed: Request = Request::new(
+        RequestId::from(4),
+        "textDocument/formatting".to_owned(),
+        json!({}),
+    );
+    client.sender.send(malformed.into()).unwrap();
+    let Message::Response(Response {
+        error: Some(error), ..
+    }) = client.receiver.recv().unwrap()
+    else {
+        panic!("expected an error");
+    };
+    assert_eq!(error.code, lsp_server::ErrorCode::InvalidParams as i32);
+
+    request(5, "shutdown", Value::Null);
+    notify("exit", Value::Null);
+    server.join().unwrap();
+}
+
+/// An empty directory of its own for a test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("whitespace-sifter-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The file names within `dir`, sorted.
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn test_write_in_place() {
+    let dir: PathBuf = temp_dir("in-place");
+    let path: PathBuf = dir.join("a.txt");
+    std::fs::write(&path, "a  b").unwrap();
+    write_in_place(&path, b"a b", Some(".bak")).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a b");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("a.txt.bak")).unwrap(),
+        "a  b"
+    );
+    assert_eq!(file_names(&dir), ["a.txt", "a.txt.bak"]);
+
+    // Renaming a file over a directory fails after the temporary file is written.
+    std::fs::create_dir(dir.join("sub")).unwrap();
+    assert!(write_in_place(&dir.join("sub"), b"a", None).is_err());
+    assert_eq!(file_names(&dir), ["a.txt", "a.txt.bak", "sub"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_in_place_permissions_and_symlinks() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir: PathBuf = temp_dir("in-place-unix");
+    let path: PathBuf = dir.join("script.sh");
+    std::fs::write(&path, "echo  a").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+    write_in_place(&path, b"echo a", None).unwrap();
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+        0o750
+    );
+
+    let link: PathBuf = dir.join("link.sh");
+    std::os::unix::fs::symlink("script.sh", &link).unwrap();
+    write_in_place(&link, b"echo b", None).unwrap();
+    assert!(std::fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "echo b");
+    assert_eq!(file_names(&dir), ["link.sh", "script.sh"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_walk() {
+    let dir: PathBuf = temp_dir("walk");
+    for (path, contents) in [
+        (".gitignore", "ignored.txt\nbuild/\n"),
+        (".ignore", "*.log\n"),
+        (".hidden.md", ""),
+        (".dir/e.md", ""),
+        ("a.md", ""),
+        ("b.txt", ""),
+        ("ignored.txt", ""),
+        ("x.log", ""),
+        ("build/out.md", ""),
+        ("docs/c.md", ""),
+        ("docs/vendor/d.md", ""),
+    ] {
+        let path: PathBuf = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("a.md", dir.join("link.md")).unwrap();
+    let walked = |include: &[&str], exclude: &[&str], hidden: bool, follow: bool| -> Vec<String> {
+        let globs = |patterns: &[&str]| {
+            let globs: Vec<Glob> = patterns
+                .iter()
+                .map(|pattern| parse_glob(pattern).unwrap())
+                .collect();
+            glob_set(&globs).unwrap()
+        };
+        let options: WalkOptions = WalkOptions {
+            include: globs(include),
+            exclude: globs(exclude),
+            hidden,
+            follow,
+        };
+        walk(&dir, &options)
+            .into_iter()
+            .map(|path| {
+                let path: PathBuf = path.unwrap();
+                path.strip_prefix(&dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    };
+    assert_eq!(
+        walked(&[], &[], false, false),
+        ["a.md", "b.txt", "docs/c.md", "docs/vendor/d.md"]
+    );
+    assert_eq!(
+        walked(&["*.md"], &["vendor"], false, false),
+        ["a.md", "docs/c.md"]
+    );
+    assert_eq!(walked(&["docs/*.md"], &[], false, false), ["docs/c.md"]);
+    assert_eq!(
+        walked(&["*.md"], &["docs"], true, false),
+        [".dir/e.md", ".hidden.md", "a.md"]
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        walked(&["*.md"], &["docs"], false, true),
+        ["a.md", "link.md"]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_git_staged() {
+    let dir: PathBuf = temp_dir("git-staged");
+    let git = |args: &[&str]| -> String {
+        let output: std::process::Output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    git(&["init", "-q"]);
+    std::fs::write(dir.join("clean.txt"), "x\n").unwrap();
+    std::fs::write(dir.join("partial.txt"), "a\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    // `partial.txt` has a staged hunk and an unstaged one, `clean.txt` only a staged one.
+    std::fs::write(dir.join("clean.txt"), "x  y\n").unwrap();
+    std::fs::write(dir.join("partial.txt"), "a  b\n").unwrap();
+    git(&["add", "."]);
+    std::fs::write(dir.join("partial.txt"), "a  b\nc\n").unwrap();
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("clean.txt", dir.join("link")).unwrap();
+        git(&["add", "link"]);
+    }
+    let head: String = git(&["rev-parse", "HEAD"]);
+    git(&[
+        "update-index",
+        "--add",
+        "--cacheinfo",
+        &format!("160000,{},submodule", head.trim_end()),
+    ]);
+
+    let files: Vec<StagedFile> = staged_files(&dir).unwrap();
+    let paths: Vec<&str> = files
+        .iter()
+        .map(|file: &StagedFile| file.path.as_str())
+        .collect();
+    assert_eq!(paths, ["clean.txt", "partial.txt"]);
+    assert_eq!(files[1].read().unwrap(), b"a  b\n");
+
+    assert!(files[0].stage(b"x y\n").unwrap());
+    assert!(!files[1].stage(b"a b\n").unwrap());
+    assert_eq!(git(&["show", ":clean.txt"]), "x y\n");
+    assert_eq!(git(&["show", ":partial.txt"]), "a b\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("clean.txt")).unwrap(),
+        "x y\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("partial.txt")).unwrap(),
+        "a  b\nc\n"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_git_staged_checks_out() {
+    let dir: PathBuf = temp_dir("git-staged-crlf");
+    let git = |args: &[&str]| -> String {
+        let output: std::process::Output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    git(&["init", "-q"]);
+    git(&["config", "core.autocrlf", "true"]);
+    std::fs::write(dir.join(".gitattributes"), "lfs.bin filter=lfs\n").unwrap();
+    std::fs::write(dir.join("crlf.txt"), "x  y\r\n").unwrap();
+    std::fs::write(dir.join("lfs.bin"), "a  b\n").unwrap();
+    git(&["add", "."]);
+
+    // Files with a `filter` attribute are skipped, as their blobs are not their contents.
+    let files: Vec<StagedFile> = staged_files(&dir).unwrap();
+    let paths: Vec<&str> = files
+        .iter()
+        .map(|file: &StagedFile| file.path.as_str())
+        .collect();
+    assert_eq!(paths, [".gitattributes", "crlf.txt"]);
+    assert_eq!(files[1].read().unwrap(), b"x  y\n");
+
+    // The working tree keeps the line endings `core.autocrlf` checks out.
+    assert!(files[1].stage(b"x y\n").unwrap());
+    assert_eq!(git(&["show", ":crlf.txt"]), "x y\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.join("crlf.txt")).unwrap(),
+        "x y\r\n"
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_input_read() {
+    let dir: PathBuf = temp_dir("input");
+    let path: PathBuf = dir.join("large.txt");
+    let text: String = "a  b\n".repeat(1 << 18);
+    std::fs::write(&path, &text).unwrap();
+    let input: Input = Input::File(path);
+    // Large files are mapped, unless they may change while sifted, as under `--watch`.
+    let mapped: Contents = input.read(true).unwrap();
+    assert!(matches!(mapped, Contents::Mapped(_)));
+    assert_eq!(&*mapped, text.as_bytes());
+    let read: Contents = input.read(false).unwrap();
+    assert!(matches!(read, Contents::Read(_)));
+    assert_eq!(&*read, text.as_bytes());
+    drop(mapped);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_next_changes() {
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+    use notify::{Event, EventKind};
+    let (sender, receiver) = std::sync::mpsc::channel::<notify::Result<Event>>();
+    let send = |kind: EventKind, path: &str| {
+        sender
+            .send(Ok(Event::new(kind).add_path(PathBuf::from(path))))
+            .unwrap();
+    };
+    send(EventKind::Modify(ModifyKind::Any), "b");
+    send(EventKind::Create(CreateKind::File), "a");
+    send(EventKind::Remove(RemoveKind::File), "c");
+    send(EventKind::Access(AccessKind::Any), "d");
+    send(EventKind::Modify(ModifyKind::Any), "b");
+    // A change within the debounce delay joins the same batch.
+    let late_sender = sender.clone();
+    let late = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        late_sender
+            .send(Ok(
+                Event::new(EventKind::Modify(ModifyKind::Any)).add_path(PathBuf::from("e"))
+            ))
+            .unwrap();
+    });
+    let changed: Vec<PathBuf> = next_changes(&receiver).unwrap().into_iter().collect();
+    late.join().unwrap();
+    assert_eq!(changed, ["a", "b", "e"].map(PathBuf::from));
+    drop(sender);
+    assert!(next_changes(&receiver).is_none());
+}
+
+#[test]
+fn test_fingerprints() {
+    let dir: PathBuf = temp_dir("fingerprints");
+    let path: PathBuf = dir.join("a.txt");
+    std::fs::write(&path, "a  b").unwrap();
+    let mut fingerprints: Fingerprints = Fingerprints::default();
+    assert!(!fingerprints.is_unchanged(&path));
+    // Sifting rewrites the file, and the event of that rewrite is then skipped.
+    write_in_place(&path, b"a b", None).unwrap();
+    fingerprints.insert(path.clone());
+    assert!(fingerprints.is_unchanged(&path));
+    std::fs::write(&path, "a b  c").unwrap();
+    assert!(!fingerprints.is_unchanged(&path));
+    std::fs::remove_file(&path).unwrap();
+    assert!(!fingerprints.is_unchanged(&path));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_skips_backups() {
+    let dir: PathBuf = temp_dir("watch");
+    let path: PathBuf = dir.join("x.txt");
+    std::fs::write(&path, "a  b").unwrap();
+    let options: WalkOptions = WalkOptions {
+        include: glob_set(&[]).unwrap(),
+        exclude: glob_set(&[backup_glob(".bak").unwrap()]).unwrap(),
+        hidden: false,
+        follow: false,
+    };
+    let (sender, receiver) = std::sync::mpsc::channel::<PathBuf>();
+    let watched: PathBuf = dir.clone();
+    // The watcher never stops, so its thread is left to end with the test binary.
+    std::thread::spawn(move || {
+        watch(&[watched], Some(&options), |path: &Path| {
+            let text: String = std::fs::read_to_string(path).unwrap();
+            write_in_place(path, text.sift().as_bytes(), Some(".bak")).unwrap();
+            sender.send(path.to_path_buf()).unwrap();
+        })
+        .unwrap();
+    });
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    std::fs::write(&path, "a  b  c").unwrap();
+    let timeout: std::time::Duration = std::time::Duration::from_secs(5);
+    assert_eq!(receiver.recv_timeout(timeout).unwrap(), path);
+    // Neither the rewrite nor its backup is sifted again.
+    assert!(receiver
+        .recv_timeout(std::time::Duration::from_secs(1))
+        .is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a b c");
+    assert_eq!(file_names(&dir), ["x.txt", "x.txt.bak"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
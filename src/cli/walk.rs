@@ -0,0 +1,64 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::{DirEntry, WalkBuilder};
+use std::path::{Path, PathBuf};
+
+/// How to walk directories for files to sift.
+pub(crate) struct WalkOptions {
+    pub(crate) include: GlobSet,
+    pub(crate) exclude: GlobSet,
+    pub(crate) hidden: bool,
+    pub(crate) follow: bool,
+}
+
+/// Parse a glob, where `*` does not match `/`.
+pub(crate) fn parse_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(pattern).literal_separator(true).build()
+}
+
+/// A glob matching the backups named with `backup_suffix`, which are never sifted themselves.
+pub(crate) fn backup_glob(backup_suffix: &str) -> Result<Glob, globset::Error> {
+    parse_glob(&format!("*{}", globset::escape(backup_suffix)))
+}
+
+pub(crate) fn glob_set(globs: &[Glob]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(glob.clone());
+    }
+    builder.build()
+}
+
+/// Whether a glob matches the file name or the path relative to `root`.
+fn is_match(globs: &GlobSet, root: &Path, path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|file_name| globs.is_match(file_name))
+        || globs.is_match(path.strip_prefix(root).unwrap_or(path))
+}
+
+/// Find the files within `root`, in the order of their names.
+/// This respects `.gitignore` and `.ignore` files, and skips hidden files and symlinks unless told otherwise.
+pub(crate) fn walk(root: &Path, options: &WalkOptions) -> Vec<Result<PathBuf, ignore::Error>> {
+    let exclude: GlobSet = options.exclude.clone();
+    let exclude_root: PathBuf = root.to_path_buf();
+    WalkBuilder::new(root)
+        .hidden(!options.hidden)
+        .follow_links(options.follow)
+        .require_git(false)
+        .sort_by_file_name(Ord::cmp)
+        .filter_entry(move |entry: &DirEntry| {
+            entry.depth() == 0 || !is_match(&exclude, &exclude_root, entry.path())
+        })
+        .build()
+        .filter_map(|entry: Result<DirEntry, ignore::Error>| match entry {
+            Ok(entry) => {
+                let is_file: bool = entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file());
+                let is_included: bool =
+                    options.include.is_empty() || is_match(&options.include, root, entry.path());
+                (is_file && is_included).then(|| Ok(entry.into_path()))
+            }
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
@@ -0,0 +1,123 @@
+use crate::cli::walk::{walk, WalkOptions};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, SystemTime};
+
+/// How long the files must stay quiet before they are sifted, as editors often save in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The modification time and length of a file, to notice the events caused by its own rewrite.
+type Fingerprint = (SystemTime, u64);
+
+fn fingerprint(path: &Path) -> Option<Fingerprint> {
+    let metadata: std::fs::Metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The fingerprints of files as they were last sifted.
+/// Sifting a file rewrites it, which is seen as yet another change.
+/// Skipping the files that have not changed since they were sifted avoids a feedback loop.
+#[derive(Default)]
+pub(crate) struct Fingerprints(HashMap<PathBuf, Fingerprint>);
+
+impl Fingerprints {
+    /// Whether the file at `path` is unchanged since it was last sifted.
+    pub(crate) fn is_unchanged(&self, path: &Path) -> bool {
+        let current: Option<Fingerprint> = fingerprint(path);
+        current.is_some() && self.0.get(path) == current.as_ref()
+    }
+
+    /// Remember the file at `path` as it is now sifted.
+    pub(crate) fn insert(&mut self, path: PathBuf) {
+        if let Some(current) = fingerprint(&path) {
+            self.0.insert(path, current);
+        }
+    }
+}
+
+/// Watch files and directories, calling `sift` with each file that changes, named as the user named it.
+/// Directories are only watched with walk options, whose filters apply to the files within them.
+/// This blocks until the watcher stops.
+pub(crate) fn watch(
+    paths: &[PathBuf],
+    options: Option<&WalkOptions>,
+    mut sift: impl FnMut(&Path),
+) -> notify::Result<()> {
+    let (sender, receiver) = channel::<notify::Result<Event>>();
+    let mut watcher: RecommendedWatcher = notify::recommended_watcher(sender)?;
+    let mut files: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut dirs: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for path in paths {
+        let canonical: PathBuf = std::fs::canonicalize(path).map_err(notify::Error::io)?;
+        if canonical.is_dir() {
+            if options.is_none() {
+                return Err(notify::Error::generic(&format!(
+                    "{} is a directory, use --recursive to watch it",
+                    path.display()
+                )));
+            }
+            watcher.watch(&canonical, RecursiveMode::Recursive)?;
+            dirs.push((path.clone(), canonical));
+        } else {
+            // Watch the parent, as editors often replace a file instead of writing to it.
+            let parent: &Path = canonical.parent().unwrap_or(&canonical);
+            watcher.watch(parent, RecursiveMode::NonRecursive)?;
+            files.insert(canonical, path.clone());
+        }
+    }
+    let mut fingerprints: Fingerprints = Fingerprints::default();
+    while let Some(changed) = next_changes(&receiver) {
+        let mut walked: HashMap<usize, HashSet<PathBuf>> = HashMap::new();
+        for path in changed {
+            let Ok(canonical) = std::fs::canonicalize(&path) else {
+                continue;
+            };
+            let name: Option<PathBuf> = files.get(&canonical).cloned().or_else(|| {
+                dirs.iter()
+                    .enumerate()
+                    .find_map(|(ind, (root, canonical_root))| {
+                        let name: PathBuf = root.join(canonical.strip_prefix(canonical_root).ok()?);
+                        let options: &WalkOptions = options?;
+                        walked
+                            .entry(ind)
+                            .or_insert_with(|| walk(root, options).into_iter().flatten().collect())
+                            .contains(&name)
+                            .then_some(name)
+                    })
+            });
+            let Some(name) = name else {
+                continue;
+            };
+            if fingerprints.is_unchanged(&canonical) {
+                continue;
+            }
+            sift(&name);
+            fingerprints.insert(canonical);
+        }
+    }
+    Ok(())
+}
+
+/// Wait for changes, then gather the paths of every change until none happen for [`DEBOUNCE`].
+/// This returns nothing once the watcher stops.
+pub(crate) fn next_changes(
+    receiver: &Receiver<notify::Result<Event>>,
+) -> Option<BTreeSet<PathBuf>> {
+    let mut event: notify::Result<Event> = receiver.recv().ok()?;
+    let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+    loop {
+        match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                changed.extend(event.paths);
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("Error watching files: {err}"),
+        }
+        match receiver.recv_timeout(DEBOUNCE) {
+            Ok(next) => event = next,
+            Err(_) => return Some(changed),
+        }
+    }
+}
@@ -16,6 +16,9 @@ fn test_old_versions() {
     std::fs::write(
         "compliance_tester_temp/src/lib.rs",
         include_str!("tests.rs")
+            .split("// Regression tests for bugs in published versions")
+            .next()
+            .expect("Failed to split test code")
             .replace(
                 "use crate::WhitespaceSifter;",
                 "#[allow(unused_imports)] use whitespace_sifter::WhitespaceSifter;",
@@ -27,7 +30,7 @@ fn test_old_versions() {
     for ver in VERSIONS.iter().rev() {
         std::fs::write(
             "compliance_tester_temp/Cargo.toml",
-            &format!(
+            format!(
                 "
                 [package]
                 name = \"compliance_tester_temp\"
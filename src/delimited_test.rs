@@ -0,0 +1,28 @@
+use crate::{DelimitedFormat, WhitespaceSifter};
+
+#[test]
+fn test_sift_delimited_csv() {
+    let input: &str = "name , note\r\n  Alice  Smith ,\"  multi\n\n  line   note  \"\r\nBob,\"say \"\"hi\"\"  now\"\r\n, \r\n";
+    let out: &str =
+        "name,note\r\nAlice Smith,\"multi\nline note\"\r\nBob,\"say \"\"hi\"\" now\"\r\n,\r\n";
+    assert_eq!(input.sift_delimited(DelimitedFormat::CSV), out);
+}
+
+#[test]
+fn test_sift_delimited_tsv() {
+    let input: &str = "  a  b \t\t c \n\t❤️   🌐\t\n";
+    let out: &str = "a b\t\tc\n\t❤️ 🌐\t\n";
+    assert_eq!(input.sift_delimited(DelimitedFormat::TSV), out);
+}
+
+#[test]
+fn test_sift_delimited_escapes() {
+    let format: DelimitedFormat = DelimitedFormat {
+        delimiter: b';',
+        quote: Some(b'\''),
+        escape: Some(b'\\'),
+    };
+    let input: &str = "a\\;  b ; ' x \\'  y ' ;c\\  \\  d\n'unterminated  \n  field";
+    let out: &str = "a\\; b;'x \\' y';c\\  \\  d\n'unterminated field";
+    assert_eq!(input.sift_delimited(format), out);
+}
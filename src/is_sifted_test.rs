@@ -0,0 +1,39 @@
+use crate::test_inputs::all_strings;
+use crate::WhitespaceSifter;
+
+#[test]
+fn test_is_sifted() {
+    assert!("".is_sifted());
+    assert!("a b\r\nc\td❤️".is_sifted());
+    assert!(!" a".is_sifted());
+    assert!(!"a\r\n".is_sifted());
+    assert!(!"a \r\nb".is_sifted());
+    assert!("a\nb\r\nc".is_sifted_preserve_newlines());
+    assert!(!"a\n\nb".is_sifted_preserve_newlines());
+}
+
+#[test]
+fn test_find_unsifted() {
+    assert_eq!("a b".find_unsifted(), None);
+    assert_eq!("a  b  c".find_unsifted(), Some(1..3));
+    assert_eq!("❤️ b\t".find_unsifted(), Some(8..9));
+    assert_eq!("a\n \r\nb".find_unsifted_preserve_newlines(), Some(1..5));
+    assert_eq!("\r\na".find_unsifted_preserve_newlines(), Some(0..2));
+}
+
+#[test]
+fn test_is_sifted_agrees_with_sift() {
+    for input in all_strings(&["a", " ", "\t", "\r", "\n", "❤"], 6) {
+        assert_eq!(input.is_sifted(), input.sift() == input, "{input:?}");
+        assert_eq!(
+            input.is_sifted_preserve_newlines(),
+            input.sift_preserve_newlines() == input,
+            "{input:?}"
+        );
+        assert!(input.sift().is_sifted(), "{input:?}");
+        assert!(
+            input.sift_preserve_newlines().is_sifted_preserve_newlines(),
+            "{input:?}"
+        );
+    }
+}
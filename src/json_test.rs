@@ -0,0 +1,23 @@
+use crate::WhitespaceSifter;
+
+#[test]
+fn test_sift_json() {
+    let input: &str = "{\r\n  \"a  b\": [ 1, 2 ,\t3 ],\n  \"c\" : { \"❤️  🌐\" : null }\n}\n";
+    let out: &str = "{\"a  b\":[1,2,3],\"c\":{\"❤️  🌐\":null}}";
+    assert_eq!(input.sift_json(), Ok(out.to_owned()));
+}
+
+#[test]
+fn test_sift_json_escapes() {
+    let input: &str = "[ \"say \\\"hi  there\\\" \" , \"back\\\\\" , \" \\\\\\\"  x\" ]";
+    let out: &str = "[\"say \\\"hi  there\\\" \",\"back\\\\\",\" \\\\\\\"  x\"]";
+    assert_eq!(input.sift_json(), Ok(out.to_owned()));
+}
+
+#[test]
+fn test_sift_json_unterminated_string() {
+    let err = "{ \"a\": \"b\\\" }".sift_json().unwrap_err();
+    assert_eq!(err.offset(), 7);
+    assert_eq!(" \"\\".sift_json().unwrap_err().offset(), 1);
+    assert_eq!(&"".sift_json().unwrap(), "");
+}
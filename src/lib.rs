@@ -2,6 +2,9 @@
 //! This crate **helps you** remove duplicate [whitespaces](https://doc.rust-lang.org/reference/whitespace.html) within a UTF-8 encoded `string`.\
 //! It naturally removes the whitespaces at the start and end of the `string`.
 //!
+//! [`WhitespaceSifter`] follows the faster [is_ascii_whitespace](https://doc.rust-lang.org/std/primitive.char.html#method.is_ascii_whitespace) implementation.
+//! [`Sifter::pattern_white_space`] follows the linked definition exactly.
+//!
 //! # Examples
 //!
 //! ```rust
@@ -22,13 +25,30 @@
 
 mod character;
 mod sift;
+mod sift_delimited;
+mod sift_json;
+mod sift_markdown;
 mod sift_preserve_newlines;
+mod sift_sql;
+mod sifter;
 mod unsafe_vec;
+mod whitespace_run;
 
-use character::{get_char_metadata, Character, CARRIAGE_RETURN, LINE_FEED};
+use character::{get_char_metadata, Character, CARRIAGE_RETURN, FORM_FEED, LINE_FEED};
 use sift::sift_preallocated;
+use sift_delimited::sift_delimited_preallocated;
+use sift_json::sift_json_preallocated;
+use sift_markdown::sift_markdown_preallocated;
 use sift_preserve_newlines::sift_preallocated_until_newline;
+use sift_sql::sift_sql_preallocated;
+use std::ops::Range;
 use unsafe_vec::{unsafe_custom_extend, unsafe_push};
+use whitespace_run::{find_unsifted_run, sift_edit_runs};
+
+pub use sift_delimited::DelimitedFormat;
+pub use sift_json::UnterminatedStringError;
+pub use sift_sql::{SqlComments, SqlDialect};
+pub use sifter::{ControlClass, ControlPolicy, LineEnding, Sifter};
 
 /// A trait containing all `string` whitespace-sifting functions.
 pub trait WhitespaceSifter: AsRef<str> {
@@ -60,7 +80,9 @@ pub trait WhitespaceSifter: AsRef<str> {
         }
         if out_vec.len() > 1 {
             let new_out_mut_len: usize = unsafe { out_vec.len().unchecked_sub(2) };
-            if unsafe { out_vec.as_ptr().add(new_out_mut_len).read() } == CARRIAGE_RETURN {
+            if unsafe { out_vec.as_ptr().add(new_out_mut_len).read() } == CARRIAGE_RETURN
+                && unsafe { out_vec.as_ptr().add(new_out_mut_len).add(1).read() } == LINE_FEED
+            {
                 unsafe { out_vec.set_len(new_out_mut_len) };
                 return out;
             }
@@ -71,6 +93,99 @@ pub trait WhitespaceSifter: AsRef<str> {
         }
         out
     }
+
+    /// This checks whether `sift` would leave a `string` implementing `AsRef<str>` unchanged, without allocating.
+    /// This stops at the first duplicate whitespace or leading or trailing whitespace.
+    #[must_use]
+    fn is_sifted(&self) -> bool {
+        self.find_unsifted().is_none()
+    }
+
+    /// This checks whether `sift_preserve_newlines` would leave a `string` implementing `AsRef<str>` unchanged, without allocating.
+    /// This stops at the first duplicate whitespace or newline, or leading or trailing whitespace.
+    #[must_use]
+    fn is_sifted_preserve_newlines(&self) -> bool {
+        self.find_unsifted_preserve_newlines().is_none()
+    }
+
+    /// This returns the byte range of the first run of whitespaces that `sift` would change, if any.
+    #[must_use]
+    fn find_unsifted(&self) -> Option<Range<usize>> {
+        let input: &str = self.as_ref();
+        find_unsifted_run(input.as_ptr(), input.len(), false)
+    }
+
+    /// This returns the byte range of the first run of whitespaces that `sift_preserve_newlines` would change, if any.
+    #[must_use]
+    fn find_unsifted_preserve_newlines(&self) -> Option<Range<usize>> {
+        let input: &str = self.as_ref();
+        find_unsifted_run(input.as_ptr(), input.len(), true)
+    }
+
+    /// This returns the edits that turn a `string` implementing `AsRef<str>` into its `sift` output, without copying it.
+    /// Each edit is a byte range of the `string` and its replacement, one for every run of whitespaces that `sift` changes.
+    /// The edits are sorted and never overlap, so applying them from last to first keeps their ranges valid.
+    #[must_use]
+    fn sift_edits(&self) -> Vec<(Range<usize>, &str)> {
+        sift_edit_runs(self.as_ref(), false)
+    }
+
+    /// This returns the edits that turn a `string` implementing `AsRef<str>` into its `sift_preserve_newlines` output, like `sift_edits`.
+    #[must_use]
+    fn sift_edits_preserve_newlines(&self) -> Vec<(Range<usize>, &str)> {
+        sift_edit_runs(self.as_ref(), true)
+    }
+
+    /// This removes duplicate [whitespaces](https://doc.rust-lang.org/reference/whitespace.html) from a [CommonMark](https://commonmark.org) `string` implementing `AsRef<str>`.
+    /// This collapses whitespaces in prose and deduplicates blank lines like `sift_preserve_newlines`.
+    /// This leaves fenced and indented code blocks, code spans, tables, list and block quote markers, and hard line breaks intact.
+    #[must_use]
+    fn sift_markdown(&self) -> String {
+        let input: &str = self.as_ref();
+        let mut out: String = String::with_capacity(input.len());
+        sift_markdown_preallocated(input, &mut out);
+        out
+    }
+
+    /// This removes all [insignificant whitespaces](https://www.rfc-editor.org/rfc/rfc8259#section-2) from a JSON `string` implementing `AsRef<str>`.
+    /// This leaves string literals untouched, including escaped quotation marks.
+    /// This does not validate the JSON any further.
+    ///
+    /// # Errors
+    ///
+    /// This returns an [`UnterminatedStringError`] holding the byte offset of the opening quotation mark of a string literal that is never closed.
+    fn sift_json(&self) -> Result<String, UnterminatedStringError> {
+        let input: &str = self.as_ref();
+        let mut out: String = String::with_capacity(input.len());
+        sift_json_preallocated(input.as_ptr(), input.len(), unsafe { out.as_mut_vec() })?;
+        Ok(out)
+    }
+
+    /// This removes duplicate [whitespaces](https://doc.rust-lang.org/reference/whitespace.html) from each field of delimited data, like CSV or TSV, implementing `AsRef<str>`.
+    /// This sifts the contents of every field on its own, including newlines within quoted fields.
+    /// This preserves record separators, delimiters, quotes and escaped characters exactly.
+    #[must_use]
+    fn sift_delimited(&self, format: DelimitedFormat) -> String {
+        let input: &str = self.as_ref();
+        let mut out: String = String::with_capacity(input.len());
+        sift_delimited_preallocated(input.as_bytes(), format, unsafe { out.as_mut_vec() });
+        out
+    }
+
+    /// This replaces every run of [whitespaces](https://doc.rust-lang.org/reference/whitespace.html) between the tokens of an SQL `string` implementing `AsRef<str>` with one space.
+    /// This leaves `'string'` literals, `"quoted identifiers"`, `$tag$ dollar-quoted $tag$` strings and comments untouched.
+    /// This keeps the newline ending a `--` comment, or removes comments entirely with [`SqlComments::Strip`].
+    /// Backslash escapes within quotes are only recognized in `E'strings'`, unless the `dialect` is [`SqlDialect::MySql`].
+    /// This makes differently formatted queries of the same shape sift to the same text.
+    #[must_use]
+    fn sift_sql(&self, comments: SqlComments, dialect: SqlDialect) -> String {
+        let input: &str = self.as_ref();
+        let mut out: String = String::with_capacity(input.len());
+        sift_sql_preallocated(input.as_bytes(), comments, dialect, unsafe {
+            out.as_mut_vec()
+        });
+        out
+    }
 }
 
 impl<T: AsRef<str>> WhitespaceSifter for T {}
@@ -118,8 +233,32 @@ pub(crate) fn sift_trim_end(out: &mut Vec<u8>, is_last_whitespace: bool) {
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod delimited_test;
+
+#[cfg(test)]
+mod is_sifted_test;
+
+#[cfg(test)]
+mod json_test;
+
+#[cfg(test)]
+mod markdown_test;
+
 #[cfg(test)]
 mod msrv_test;
 
+#[cfg(test)]
+mod sift_edits_test;
+
+#[cfg(test)]
+mod sifter_test;
+
+#[cfg(test)]
+mod sql_test;
+
+#[cfg(test)]
+mod test_inputs;
+
 #[cfg(test)]
 mod compliance_test;
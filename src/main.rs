@@ -1,7 +1,30 @@
-use clap::Parser;
-use std::io::Read;
+mod cli;
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use cli::check::line_column;
+use cli::decode::{decode, parse_encoding, Decoded, EncodingArg};
+use cli::diff::{unified_diff, ColorChoice};
+use cli::git::staged_files;
+use cli::in_place::write_in_place;
+use cli::input::{Contents, Input};
+use cli::lines::{find_unsifted_lines, parse_line_range, sift_edits_lines, sift_lines, LineRange};
+use cli::lsp::serve;
+use cli::pool::run_ordered;
+use cli::records::{find_unsifted_records, parse_separator, sift_edits_records, sift_records};
+use cli::stats::{report, Stats, StatsFormat};
+use cli::stream::stream;
+use cli::walk::{backup_glob, glob_set, parse_glob, walk, WalkOptions};
+use cli::watch::watch;
+use globset::Glob;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use whitespace_sifter::WhitespaceSifter;
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser)]
 #[command(
     name = "whitespace-sifter",
@@ -10,32 +33,485 @@ use whitespace_sifter::WhitespaceSifter;
     version
 )]
 struct Args {
-    /// Reads from stdin if omitted
-    input: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Files to sift, reads from stdin if omitted or `-`
+    files: Vec<PathBuf>,
+
+    /// Sift the files within directories, respecting `.gitignore` and `.ignore` files
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only sift the files matching this glob when walking directories
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob, requires = "recursive")]
+    include: Vec<Glob>,
+
+    /// Skip the files and directories matching this glob when walking directories
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob, requires = "recursive")]
+    exclude: Vec<Glob>,
+
+    /// Also sift hidden files and directories when walking directories
+    #[arg(long, requires = "recursive")]
+    hidden: bool,
+
+    /// Follow symlinks when walking directories
+    #[arg(long, requires = "recursive")]
+    follow: bool,
+
+    /// Sift this text instead of files or stdin
+    #[arg(long, conflicts_with = "files")]
+    text: Option<String>,
 
     /// Preserve newlines
     #[arg(long)]
     preserve_newlines: bool,
+
+    /// Sift each NUL-separated record on its own, like `--record-separator '\0'`
+    #[arg(short, long, conflicts_with = "lines")]
+    zero_terminated: bool,
+
+    /// Sift each record separated by this separator on its own, keeping the separators
+    #[arg(long, value_name = "SEP", value_parser = parse_separator, conflicts_with_all = ["lines", "zero_terminated"])]
+    record_separator: Option<String>,
+
+    /// Sift stdin line by line, writing each line as soon as it is complete, for long-running pipelines
+    #[arg(long, conflicts_with_all = ["files", "text", "lines", "zero_terminated", "record_separator", "encoding", "check", "diff", "stats", "stats_format"])]
+    line_buffered: bool,
+
+    /// Only sift these lines, preserving newlines, and leave every other byte untouched
+    #[arg(long, value_name = "START:END", value_parser = parse_line_range)]
+    lines: Vec<LineRange>,
+
+    /// Rewrite the files in place instead of printing them
+    #[arg(short, long, requires = "files")]
+    in_place: bool,
+
+    /// Sift up to this many files at once, still reporting them in order
+    #[arg(short, long, value_name = "N", default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Keep sifting the files in place whenever they change, until interrupted
+    #[arg(long, requires = "in_place", conflicts_with_all = ["stats", "stats_format"])]
+    watch: bool,
+
+    /// Keep a copy of each original file, named with this suffix appended
+    #[arg(long, requires = "in_place", value_name = "SUFFIX")]
+    backup_suffix: Option<String>,
+
+    /// Sift files that are not valid UTF-8, leaving undecodable bytes untouched, unless they are decoded from another encoding
+    #[arg(long)]
+    lossy: bool,
+
+    /// Decode the inputs from this encoding, or detect UTF-8 and UTF-16 by their byte order mark with `auto`
+    #[arg(long, value_name = "ENCODING", value_parser = parse_encoding)]
+    encoding: Option<EncodingArg>,
+
+    /// Write the output as UTF-8 instead of the encoding of the input
+    #[arg(long, requires = "encoding")]
+    output_utf8: bool,
+
+    /// Report bytes, collapsed runs, trimmed lines and line endings of every input to stderr
+    #[arg(long)]
+    stats: bool,
+
+    /// The format of the stats report, implies `--stats`
+    #[arg(long, value_name = "FORMAT")]
+    stats_format: Option<StatsFormat>,
+
+    /// Rewrite nothing, list the inputs that would change and exit with 1 if there are any
+    #[arg(long, conflicts_with = "in_place")]
+    check: bool,
+
+    /// Also report the line and column of the first whitespace that would change
+    #[arg(long, requires = "check", conflicts_with = "diff")]
+    location: bool,
+
+    /// Rewrite nothing, print a unified diff between each input and its sifted output
+    #[arg(long, conflicts_with = "in_place")]
+    diff: bool,
+
+    /// When to color the diff
+    #[arg(long, value_name = "WHEN", default_value = "auto", requires = "diff")]
+    color: ColorChoice,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Sift the files staged in the git index, for use as a pre-commit hook
+    ///
+    /// This lists the staged files that would change and exits with 1 if there are any, unless `--fix` is given.
+    GitStaged {
+        /// Restage the sifted files, also rewriting them in the working tree unless they have unstaged changes
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Print a completion script for a shell
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Print a roff man page
+    Man,
+
+    /// Serve the Language Server Protocol over stdio, for formatting and diagnostics in editors
+    ///
+    /// Formatting and diagnostics always preserve newlines, as if `--preserve-newlines` were given,
+    /// so formatting a document never joins it into one line.
+    Lsp,
+}
+
+impl Args {
+    fn inputs(&self) -> Vec<std::io::Result<Input>> {
+        if let Some(Command::GitStaged { .. }) = &self.command {
+            return match staged_files(Path::new(".")) {
+                Ok(files) => files
+                    .into_iter()
+                    .map(|file| Ok(Input::Staged(file)))
+                    .collect(),
+                Err(err) => vec![Err(err)],
+            };
+        }
+        if let Some(text) = &self.text {
+            return vec![Ok(Input::Text(text.clone()))];
+        }
+        if self.files.is_empty() {
+            return vec![Ok(Input::Stdin)];
+        }
+        let options: Option<WalkOptions> = self.recursive.then(|| self.walk_options());
+        let mut inputs: Vec<std::io::Result<Input>> = Vec::with_capacity(self.files.len());
+        for path in &self.files {
+            match &options {
+                _ if path == Path::new("-") => inputs.push(Ok(Input::Stdin)),
+                Some(options) if path.is_dir() => inputs.extend(
+                    walk(path, options)
+                        .into_iter()
+                        .map(|path| path.map(Input::File).map_err(std::io::Error::other)),
+                ),
+                _ => inputs.push(Ok(Input::File(path.clone()))),
+            }
+        }
+        inputs
+    }
+
+    fn walk_options(&self) -> WalkOptions {
+        // Skip the backups of earlier rewrites, or each run and each change seen by `--watch` backs up the backups.
+        let exclude: Result<Vec<Glob>, globset::Error> = self
+            .backup_suffix
+            .as_deref()
+            .map(backup_glob)
+            .into_iter()
+            .chain(self.exclude.iter().cloned().map(Ok))
+            .collect();
+        let (include, exclude) = match (
+            glob_set(&self.include),
+            exclude.and_then(|exclude| glob_set(&exclude)),
+        ) {
+            (Ok(include), Ok(exclude)) => (include, exclude),
+            (Err(err), _) | (_, Err(err)) => {
+                eprintln!("Error building globs: {err}");
+                std::process::exit(2);
+            }
+        };
+        WalkOptions {
+            include,
+            exclude,
+            hidden: self.hidden,
+            follow: self.follow,
+        }
+    }
+
+    fn record_separator(&self) -> Option<&str> {
+        if self.zero_terminated {
+            return Some("\0");
+        }
+        self.record_separator.as_deref()
+    }
+
+    fn sift(&self, input: &str) -> String {
+        match self.record_separator() {
+            Some(separator) => {
+                sift_records(input, separator, |record: &str| self.sift_record(record))
+            }
+            None => self.sift_record(input),
+        }
+    }
+
+    fn sift_record(&self, input: &str) -> String {
+        if !self.lines.is_empty() {
+            return sift_lines(input, &self.lines);
+        }
+        if self.preserve_newlines {
+            return input.sift_preserve_newlines();
+        }
+        input.sift()
+    }
+
+    /// The byte ranges that [`Args::sift`] edits.
+    fn sift_edits(&self, input: &str) -> Vec<Range<usize>> {
+        match self.record_separator() {
+            Some(separator) => sift_edits_records(input, separator, |record: &str| {
+                self.sift_edits_record(record)
+            }),
+            None => self.sift_edits_record(input),
+        }
+    }
+
+    fn sift_edits_record(&self, input: &str) -> Vec<Range<usize>> {
+        if !self.lines.is_empty() {
+            return sift_edits_lines(input, &self.lines);
+        }
+        let edits: Vec<(Range<usize>, &str)> = if self.preserve_newlines {
+            input.sift_edits_preserve_newlines()
+        } else {
+            input.sift_edits()
+        };
+        edits.into_iter().map(|(edit, _)| edit).collect()
+    }
+
+    fn stats_format(&self) -> Option<StatsFormat> {
+        self.stats_format
+            .or_else(|| self.stats.then_some(StatsFormat::Text))
+    }
+
+    fn find_unsifted(&self, input: &str) -> Option<Range<usize>> {
+        match self.record_separator() {
+            Some(separator) => find_unsifted_records(input, separator, |record: &str| {
+                self.find_unsifted_record(record)
+            }),
+            None => self.find_unsifted_record(input),
+        }
+    }
+
+    fn find_unsifted_record(&self, input: &str) -> Option<Range<usize>> {
+        if !self.lines.is_empty() {
+            return find_unsifted_lines(input, &self.lines);
+        }
+        if self.preserve_newlines {
+            return input.find_unsifted_preserve_newlines();
+        }
+        input.find_unsifted()
+    }
 }
 
 fn main() {
-    let args: Args = Args::parse();
-
-    let input: String = if let Some(val) = args.input {
-        val
-    } else {
-        let mut buf = String::new();
-        if let Err(err) = std::io::stdin().read_to_string(&mut buf) {
-            eprintln!("Error reading from stdin: {err}");
-            std::process::exit(1);
+    let mut args: Args = Args::parse();
+    if args.command.is_some() && (!args.files.is_empty() || args.text.is_some()) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "files and `--text` cannot be used with a subcommand",
+            )
+            .exit();
+    }
+    match args.command {
+        Some(Command::GitStaged { fix: false }) => args.check = true,
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(
+                shell,
+                &mut Args::command(),
+                "whitespace-sifter",
+                &mut std::io::stdout(),
+            );
+            return;
         }
-        buf
-    };
+        Some(Command::Man) => {
+            if let Err(err) = clap_mangen::Man::new(Args::command()).render(&mut std::io::stdout())
+            {
+                eprintln!("Error writing the man page: {err}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Lsp) => {
+            let (connection, io_threads) = lsp_server::Connection::stdio();
+            let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = serve(&connection);
+            drop(connection);
+            if let Err(err) = result.and(io_threads.join().map_err(Into::into)) {
+                eprintln!("Error serving the language server: {err}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ => {}
+    }
 
-    if args.preserve_newlines {
-        print!("{}", input.sift_preserve_newlines());
+    if args.line_buffered {
+        if let Err(err) = stream(
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+            args.preserve_newlines,
+            args.lossy,
+        ) {
+            eprintln!("Error sifting <stdin>: {err}");
+            std::process::exit(1);
+        }
         return;
     }
 
-    print!("{}", input.sift());
+    let mut is_failed: bool = false;
+    let mut is_changed: bool = false;
+    let mut stats: Vec<(String, Stats)> = Vec::new();
+    run_ordered(
+        &args.inputs(),
+        args.jobs,
+        |input: &std::io::Result<Input>| {
+            let mut log: Log = Log::default();
+            let outcome: Option<std::io::Result<Option<Outcome>>> = input
+                .as_ref()
+                .ok()
+                .map(|input: &Input| sift_input(&args, input, &mut log));
+            (log, outcome)
+        },
+        |input: &std::io::Result<Input>, (log, outcome)| {
+            log.print();
+            let input: &Input = match input {
+                Ok(input) => input,
+                Err(err) => {
+                    eprintln!("Error finding inputs: {err}");
+                    is_failed = true;
+                    return;
+                }
+            };
+            match outcome {
+                Some(Ok(Some(outcome))) => {
+                    is_changed |= outcome.is_changed;
+                    if let Some(outcome_stats) = outcome.stats {
+                        stats.push((input.name(), outcome_stats));
+                    }
+                }
+                Some(Ok(None)) | None => {}
+                Some(Err(err)) => {
+                    eprintln!("Error sifting {}: {err}", input.name());
+                    // Before `v3.0.0`, the argument was the text to sift.
+                    if err.kind() == std::io::ErrorKind::NotFound
+                        && matches!(input, Input::File(path) if path.to_string_lossy().contains(char::is_whitespace))
+                    {
+                        eprintln!("Use `--text` to sift text instead of a file");
+                    }
+                    is_failed = true;
+                }
+            }
+        },
+    );
+    if args.watch {
+        let options: Option<WalkOptions> = args.recursive.then(|| args.walk_options());
+        let result: notify::Result<()> = watch(&args.files, options.as_ref(), |path: &Path| {
+            let input: Input = Input::File(path.to_path_buf());
+            let mut log: Log = Log::default();
+            let result: std::io::Result<Option<Outcome>> = sift_input(&args, &input, &mut log);
+            log.print();
+            if let Err(err) = result {
+                eprintln!("Error sifting {}: {err}", input.name());
+            }
+        });
+        if let Err(err) = result {
+            eprintln!("Error watching files: {err}");
+            std::process::exit(1);
+        }
+    }
+    if let Some(format) = args.stats_format() {
+        eprint!("{}", report(format, &stats));
+    }
+    if is_failed || (args.check && is_changed) {
+        std::process::exit(1);
+    }
+}
+
+/// What sifting one input did.
+struct Outcome {
+    is_changed: bool,
+    stats: Option<Stats>,
+}
+
+/// What sifting one input printed, held back so that inputs sifted at once are still reported in order.
+#[derive(Default)]
+struct Log {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl Log {
+    fn print(&self) {
+        // Like `print!`, give up quietly on a closed pipe rather than failing every input after it.
+        let _ = std::io::stdout().lock().write_all(&self.stdout);
+        let _ = std::io::stderr().lock().write_all(&self.stderr);
+    }
+}
+
+/// Sift one input, returning nothing if it is skipped.
+fn sift_input(args: &Args, input: &Input, log: &mut Log) -> std::io::Result<Option<Outcome>> {
+    // A watched file changes under its writer, so it is read instead of mapped.
+    let contents: Contents = input.read(!args.watch)?;
+    let bytes_in: usize = contents.len();
+    let decoded: Decoded = match decode(
+        &contents,
+        args.encoding,
+        args.lossy,
+        args.record_separator()
+            .is_some_and(|separator: &str| separator.contains('\0')),
+    ) {
+        Ok(decoded) => decoded,
+        Err(reason) => {
+            writeln!(log.stderr, "Skipping {}: {reason}", input.name())?;
+            return Ok(None);
+        }
+    };
+    let text: &str = &decoded.text;
+    if args.check && !args.diff {
+        let unsifted: Option<Range<usize>> = args.find_unsifted(text);
+        if let Some(unsifted) = &unsifted {
+            if args.location {
+                let (line, column): (usize, usize) = line_column(text, unsifted.start);
+                writeln!(log.stdout, "{}:{line}:{column}", input.name())?;
+            } else {
+                writeln!(log.stdout, "{}", input.name())?;
+            }
+        }
+        if args.stats_format().is_none() {
+            return Ok(Some(Outcome {
+                is_changed: unsifted.is_some(),
+                stats: None,
+            }));
+        }
+    }
+    let output: String = args.sift(text);
+    let is_changed: bool = output != text;
+    if args.diff {
+        let diff: String = unified_diff(&input.name(), text, &output, args.color.is_enabled());
+        log.stdout.extend_from_slice(diff.as_bytes());
+    }
+    let is_reencoded: bool = decoded.is_reencoded(args.output_utf8);
+    let mut stats: Option<Stats> = args
+        .stats_format()
+        .map(|_| Stats::new(text, &args.sift_edits(text), &output));
+    let output: Vec<u8> = decoded.encode(output, args.output_utf8);
+    if let Some(stats) = &mut stats {
+        stats.bytes_in = bytes_in;
+        stats.bytes_out = output.len();
+    }
+    // Unmap the input before replacing it, which some platforms forbid while it is mapped.
+    drop(decoded);
+    drop(contents);
+    match input {
+        _ if args.check || args.diff => {}
+        Input::File(path) if args.in_place => {
+            if is_changed || is_reencoded {
+                write_in_place(path, &output, args.backup_suffix.as_deref())?;
+            }
+        }
+        Input::Staged(file) => {
+            if (is_changed || is_reencoded) && !file.stage(&output)? {
+                writeln!(
+                    log.stderr,
+                    "Restaged {}, leaving its unstaged changes in the working tree as they are",
+                    file.path
+                )?;
+            }
+        }
+        _ => log.stdout.extend_from_slice(&output),
+    }
+    Ok(Some(Outcome { is_changed, stats }))
 }
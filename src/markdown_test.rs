@@ -0,0 +1,31 @@
+use crate::WhitespaceSifter;
+
+#[test]
+fn test_sift_markdown_prose() {
+    let input: &str =
+        "\n\n#   Title  \n\nSome   prose\t\twith  `code   span`.\n\n\n\nNext  paragraph.\n\n";
+    let out: &str = "# Title\n\nSome prose\twith `code   span`.\n\nNext paragraph.";
+    assert_eq!(input.sift_markdown(), out);
+}
+
+#[test]
+fn test_sift_markdown_code_blocks() {
+    let input: &str = "Intro  text\r\n\r\n```rust\r\nfn  main()  {\r\n\r\n\r\n    let  x = 1;  \r\n}\r\n```  \r\n\r\n\r\n    indented   code\r\n\r\n\r\n    more   code\r\n\r\n\r\nDone.";
+    let out: &str = "Intro text\r\n\r\n```rust\r\nfn  main()  {\r\n\r\n\r\n    let  x = 1;  \r\n}\r\n```\r\n\r\n    indented   code\r\n\r\n\r\n    more   code\r\n\r\nDone.";
+    assert_eq!(input.sift_markdown(), out);
+}
+
+#[test]
+fn test_sift_markdown_lists_and_breaks() {
+    let input: &str = "1.  First   item  \n    continued    line\n-   Bullet\n    - Nested   bullet\n\n> Quoted   text   \n>\n>     quoted  code";
+    let out: &str = "1.  First item  \n    continued line\n-   Bullet\n    - Nested bullet\n\n> Quoted text\n>\n>     quoted  code";
+    assert_eq!(input.sift_markdown(), out);
+}
+
+#[test]
+fn test_sift_markdown_tables() {
+    let input: &str =
+        "| Name   | Value |\n| :----- | ----: |\n| a      |     1 |   \n\nAfter   table";
+    let out: &str = "| Name   | Value |\n| :----- | ----: |\n| a      |     1 |\n\nAfter table";
+    assert_eq!(input.sift_markdown(), out);
+}
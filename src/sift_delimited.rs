@@ -0,0 +1,129 @@
+use crate::{CARRIAGE_RETURN, LINE_FEED};
+
+/// The dialect of delimited data sifted by `sift_delimited`.
+/// All of its characters must be ASCII.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DelimitedFormat {
+    /// The character separating fields within a record.
+    pub delimiter: u8,
+    /// The character enclosing quoted fields, if quoting is supported.
+    pub quote: Option<u8>,
+    /// The character escaping the character after it.
+    /// This may equal `quote`, in which case doubled quotes are escaped quotes.
+    pub escape: Option<u8>,
+}
+
+impl DelimitedFormat {
+    /// Comma-separated values as described by [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180).
+    pub const CSV: Self = Self {
+        delimiter: b',',
+        quote: Some(b'"'),
+        escape: Some(b'"'),
+    };
+
+    /// Tab-separated values as described by [IANA](https://www.iana.org/assignments/media-types/text/tab-separated-values).
+    pub const TSV: Self = Self {
+        delimiter: b'\t',
+        quote: None,
+        escape: None,
+    };
+}
+
+/// Sifts the contents of a single field, deferring each whitespace until something follows it.
+struct FieldSifter {
+    is_start: bool,
+    pending_whitespace: Option<u8>,
+}
+
+impl FieldSifter {
+    const fn new() -> Self {
+        Self {
+            is_start: true,
+            pending_whitespace: None,
+        }
+    }
+
+    fn push_whitespace(&mut self, byte: u8) {
+        if !self.is_start && self.pending_whitespace.is_none() {
+            self.pending_whitespace = Some(byte);
+        }
+    }
+
+    fn push_content(&mut self, out: &mut Vec<u8>, bytes: &[u8]) {
+        if let Some(whitespace) = self.pending_whitespace.take() {
+            out.push(whitespace);
+        }
+        out.extend_from_slice(bytes);
+        self.is_start = false;
+    }
+}
+
+/// A utility for `sift_delimited`.
+pub(crate) fn sift_delimited_preallocated(
+    input: &[u8],
+    format: DelimitedFormat,
+    out: &mut Vec<u8>,
+) {
+    let mut ind: usize = 0;
+    let mut field: FieldSifter = FieldSifter::new();
+    let mut is_quoted: bool = false;
+    while ind < input.len() {
+        let byte: u8 = input[ind];
+        let escape_len: usize = if ind + 1 < input.len() { 2 } else { 1 };
+        if is_quoted {
+            if Some(byte) == format.escape && format.escape == format.quote {
+                if input.get(ind + 1).copied() == format.quote {
+                    field.push_content(out, &input[ind..ind + 2]);
+                    ind += 2;
+                    continue;
+                }
+            } else if Some(byte) == format.escape {
+                field.push_content(out, &input[ind..ind + escape_len]);
+                ind += escape_len;
+                continue;
+            }
+            if Some(byte) == format.quote {
+                // Whitespaces before the closing quote are trimmed.
+                field.pending_whitespace = None;
+                out.push(byte);
+                field.is_start = false;
+                is_quoted = false;
+            } else if byte.is_ascii_whitespace() {
+                field.push_whitespace(byte);
+            } else {
+                field.push_content(out, &input[ind..=ind]);
+            }
+            ind += 1;
+            continue;
+        }
+        if byte == format.delimiter {
+            out.push(byte);
+            field = FieldSifter::new();
+            ind += 1;
+            continue;
+        }
+        if byte == LINE_FEED || (byte == CARRIAGE_RETURN && input.get(ind + 1) == Some(&LINE_FEED))
+        {
+            let separator_len: usize = if byte == LINE_FEED { 1 } else { 2 };
+            out.extend_from_slice(&input[ind..ind + separator_len]);
+            field = FieldSifter::new();
+            ind += separator_len;
+            continue;
+        }
+        if Some(byte) == format.quote && field.is_start {
+            // Whitespaces before the opening quote are dropped.
+            field.pending_whitespace = None;
+            out.push(byte);
+            is_quoted = true;
+        } else if Some(byte) == format.escape && format.escape != format.quote {
+            field.push_content(out, &input[ind..ind + escape_len]);
+            ind += escape_len;
+            continue;
+        } else if byte.is_ascii_whitespace() {
+            field.push_whitespace(byte);
+        } else {
+            field.push_content(out, &input[ind..=ind]);
+        }
+        ind += 1;
+    }
+}
@@ -0,0 +1,48 @@
+use crate::test_inputs::all_strings;
+use crate::WhitespaceSifter;
+use std::ops::Range;
+
+fn apply(input: &str, edits: &[(Range<usize>, &str)]) -> String {
+    let mut out: String = input.to_owned();
+    for (range, replacement) in edits.iter().rev() {
+        out.replace_range(range.clone(), replacement);
+    }
+    out
+}
+
+#[test]
+fn test_sift_edits() {
+    assert_eq!("a b".sift_edits(), []);
+    assert_eq!(
+        " a  b\t\tc \n".sift_edits(),
+        [(0..1, ""), (3..4, ""), (6..7, ""), (8..10, "")]
+    );
+    assert_eq!("a \n\n b".sift_edits_preserve_newlines(), [(1..5, "\n")]);
+    assert_eq!("a\r\n \n b".sift_edits_preserve_newlines(), [(3..6, "")]);
+    assert_eq!("a\t\n \nb".sift_edits_preserve_newlines(), [(1..5, "\n")]);
+    assert_eq!("a\r\r\nb".sift_edits(), [(2..3, "")]);
+    assert_eq!("a\r \n\tb".sift_edits(), [(2..5, "\n")]);
+}
+
+#[test]
+fn test_sift_edits_agree_with_sift() {
+    for input in all_strings(&["a", " ", "\t", "\r", "\n", "❤"], 6) {
+        for (edits, sifted) in [
+            (input.sift_edits(), input.sift()),
+            (
+                input.sift_edits_preserve_newlines(),
+                input.sift_preserve_newlines(),
+            ),
+        ] {
+            assert_eq!(apply(&input, &edits), sifted, "{input:?}");
+            // Edits are sorted, and separated by content since each run gets at most one.
+            for pair in edits.windows(2) {
+                let between: &str = &input[pair[0].0.end..pair[1].0.start];
+                assert!(
+                    between.contains(|ch: char| !ch.is_ascii_whitespace()),
+                    "{input:?}"
+                );
+            }
+        }
+    }
+}
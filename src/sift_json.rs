@@ -0,0 +1,89 @@
+use crate::{get_char_metadata, unsafe_custom_extend, Character, FORM_FEED};
+
+/// The error returned by `sift_json` when a string literal is never closed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnterminatedStringError {
+    offset: usize,
+}
+
+impl UnterminatedStringError {
+    /// The byte offset of the opening quotation mark of the unterminated string literal.
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl std::fmt::Display for UnterminatedStringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unterminated string literal starting at byte {}",
+            self.offset
+        )
+    }
+}
+
+impl std::error::Error for UnterminatedStringError {}
+
+#[allow(clippy::cast_possible_truncation)]
+const QUOTATION_MARK: u8 = '"' as u32 as u8;
+#[allow(clippy::cast_possible_truncation)]
+const REVERSE_SOLIDUS: u8 = '\\' as u32 as u8;
+
+/// A utility for `sift_json`.
+pub(crate) fn sift_json_preallocated(
+    in_ptr: *const u8,
+    in_len: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), UnterminatedStringError> {
+    let mut ind: usize = 0;
+    let mut copy_len: usize = 0;
+    while ind < in_len {
+        let byte: u8 = unsafe { in_ptr.add(ind).read() };
+        match get_char_metadata(byte) {
+            Character::NormalWhitespace if byte == FORM_FEED => {
+                ind = unsafe { ind.unchecked_add(1) };
+                copy_len = unsafe { copy_len.unchecked_add(1) };
+            }
+            Character::NormalWhitespace | Character::LineFeed | Character::CarriageReturn => {
+                unsafe {
+                    unsafe_custom_extend(out, in_ptr.add(ind).sub(copy_len), copy_len);
+                }
+                copy_len = 0;
+                ind = unsafe { ind.unchecked_add(1) };
+            }
+            Character::SingleByte if byte == QUOTATION_MARK => {
+                let start: usize = ind;
+                ind = unsafe { ind.unchecked_add(1) };
+                // Both delimiters are ASCII, so the string literal can be skipped byte by byte.
+                loop {
+                    if ind >= in_len {
+                        return Err(UnterminatedStringError { offset: start });
+                    }
+                    match unsafe { in_ptr.add(ind).read() } {
+                        REVERSE_SOLIDUS => ind = unsafe { ind.unchecked_add(2) },
+                        QUOTATION_MARK => {
+                            ind = unsafe { ind.unchecked_add(1) };
+                            break;
+                        }
+                        _ => ind = unsafe { ind.unchecked_add(1) },
+                    }
+                }
+                copy_len = unsafe { copy_len.unchecked_add(ind.unchecked_sub(start)) };
+            }
+            Character::SingleByte => {
+                ind = unsafe { ind.unchecked_add(1) };
+                copy_len = unsafe { copy_len.unchecked_add(1) };
+            }
+            Character::MultiByte { len } => {
+                ind = unsafe { ind.unchecked_add(len as usize) };
+                copy_len = unsafe { copy_len.unchecked_add(len as usize) };
+            }
+        }
+    }
+    unsafe {
+        unsafe_custom_extend(out, in_ptr.add(ind).sub(copy_len), copy_len);
+    }
+    Ok(())
+}
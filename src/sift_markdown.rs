@@ -0,0 +1,309 @@
+use crate::{CARRIAGE_RETURN, LINE_FEED};
+
+/// A line split from its line ending.
+struct Line<'a> {
+    body: &'a str,
+    ending: &'a str,
+}
+
+/// An open fenced code block, remembered by its marker and length.
+struct Fence {
+    marker: u8,
+    len: usize,
+}
+
+/// A utility for `sift_markdown`.
+pub(crate) fn sift_markdown_preallocated(input: &str, out: &mut String) {
+    let lines: Vec<Line<'_>> = split_lines(input);
+    let mut fence: Option<Fence> = None;
+    let mut in_indented_code: bool = false;
+    let mut in_table: bool = false;
+    let mut is_last_paragraph: bool = false;
+    // Blank lines are held back until the next non-blank line decides their fate.
+    let mut blank_run: usize = 0;
+    let mut blank_start: usize = 0;
+    for (ind, line) in lines.iter().enumerate() {
+        if let Some(open) = &fence {
+            let rest: &str = &line.body[quote_prefix_len(line.body)..];
+            if is_fence_close(rest, open) {
+                fence = None;
+                push_line(out, line.body.trim_end(), line.ending);
+            } else {
+                push_line(out, line.body, line.ending);
+            }
+            continue;
+        }
+        let prefix_len: usize = quote_prefix_len(line.body);
+        let (prefix, rest): (&str, &str) = line.body.split_at(prefix_len);
+        if is_blank(rest) {
+            if prefix.is_empty() {
+                if blank_run == 0 {
+                    blank_start = ind;
+                }
+                blank_run += 1;
+            } else {
+                flush_blank_run(out, &lines, &mut blank_run, blank_start, false);
+                push_line(out, prefix.trim_end(), line.ending);
+            }
+            in_table = false;
+            is_last_paragraph = false;
+            continue;
+        }
+        let (indent_len, indent_width): (usize, usize) = leading_indent(rest);
+        if indent_width >= 4 && !is_last_paragraph && !in_table {
+            flush_blank_run(out, &lines, &mut blank_run, blank_start, in_indented_code);
+            in_indented_code = true;
+            push_line(out, line.body, line.ending);
+            continue;
+        }
+        flush_blank_run(out, &lines, &mut blank_run, blank_start, false);
+        in_indented_code = false;
+        if let Some(open) = fence_open(&rest[indent_len..]) {
+            fence = Some(open);
+            in_table = false;
+            is_last_paragraph = false;
+            push_line(out, line.body.trim_end(), line.ending);
+            continue;
+        }
+        if in_table
+            || (rest.contains('|')
+                && lines
+                    .get(ind + 1)
+                    .is_some_and(|next| is_table_delimiter_row(next.body)))
+        {
+            in_table = true;
+            is_last_paragraph = false;
+            push_line(out, line.body.trim_end(), line.ending);
+            continue;
+        }
+        // Indented code blocks cannot interrupt a paragraph, but they can follow a heading.
+        is_last_paragraph = !is_atx_heading(&rest[indent_len..]);
+        out.push_str(prefix);
+        out.push_str(&rest[..indent_len]);
+        let rest: &str = &rest[indent_len..];
+        let marker_len: usize = list_marker_len(rest);
+        out.push_str(&rest[..marker_len]);
+        let content: &str = &rest[marker_len..];
+        sift_prose(content, out);
+        let has_next_line: bool = lines
+            .get(ind + 1)
+            .is_some_and(|next| !is_blank(&next.body[quote_prefix_len(next.body)..]));
+        if has_next_line && content.trim_end_matches(' ').len() + 2 <= content.len() {
+            // A hard line break.
+            out.push_str("  ");
+        }
+        out.push_str(line.ending);
+    }
+    // Implementation of str::trim_end()
+    if fence.is_none() {
+        let trimmed_len: usize = out.trim_end_matches(['\r', '\n']).len();
+        out.truncate(trimmed_len);
+    } else if out.ends_with('\n') {
+        out.pop();
+        if out.ends_with('\r') {
+            out.pop();
+        }
+    }
+}
+
+/// Split the input into lines, keeping track of `LF` and `CR-LF` endings.
+fn split_lines(input: &str) -> Vec<Line<'_>> {
+    input
+        .split_inclusive('\n')
+        .map(|line: &str| {
+            let bytes: &[u8] = line.as_bytes();
+            let ending_len: usize = match bytes {
+                [.., CARRIAGE_RETURN, LINE_FEED] => 2,
+                [.., LINE_FEED] => 1,
+                _ => 0,
+            };
+            let (body, ending): (&str, &str) = line.split_at(line.len() - ending_len);
+            Line { body, ending }
+        })
+        .collect()
+}
+
+/// Emit a held-back run of blank lines.
+/// The run collapses into one blank line unless it sits inside an indented code block.
+fn flush_blank_run(
+    out: &mut String,
+    lines: &[Line],
+    blank_run: &mut usize,
+    blank_start: usize,
+    is_code: bool,
+) {
+    if *blank_run == 0 {
+        return;
+    }
+    if is_code {
+        for line in &lines[blank_start..blank_start + *blank_run] {
+            push_line(out, line.body, line.ending);
+        }
+    } else if !out.is_empty() {
+        out.push_str(lines[blank_start].ending);
+    }
+    *blank_run = 0;
+}
+
+fn push_line(out: &mut String, body: &str, ending: &str) {
+    out.push_str(body);
+    out.push_str(ending);
+}
+
+fn is_blank(line: &str) -> bool {
+    line.bytes().all(|byte: u8| byte.is_ascii_whitespace())
+}
+
+/// The byte length and column width of the leading spaces and tabs.
+fn leading_indent(line: &str) -> (usize, usize) {
+    let mut width: usize = 0;
+    for (ind, byte) in line.bytes().enumerate() {
+        match byte {
+            b' ' => width += 1,
+            b'\t' => width += 4 - width % 4,
+            _ => return (ind, width),
+        }
+    }
+    (line.len(), width)
+}
+
+/// The byte length of any leading block quote markers, including their optional space.
+fn quote_prefix_len(line: &str) -> usize {
+    let bytes: &[u8] = line.as_bytes();
+    let mut len: usize = 0;
+    loop {
+        let (indent_len, indent_width): (usize, usize) = leading_indent(&line[len..]);
+        if indent_width > 3 || bytes.get(len + indent_len) != Some(&b'>') {
+            return len;
+        }
+        len += indent_len + 1;
+        if bytes.get(len) == Some(&b' ') {
+            len += 1;
+        }
+    }
+}
+
+/// The fence that this line opens, if it is a code fence.
+fn fence_open(rest: &str) -> Option<Fence> {
+    let marker: u8 = *rest.as_bytes().first()?;
+    if marker != b'`' && marker != b'~' {
+        return None;
+    }
+    let len: usize = rest.bytes().take_while(|byte: &u8| *byte == marker).count();
+    if len < 3 || (marker == b'`' && rest[len..].contains('`')) {
+        return None;
+    }
+    Some(Fence { marker, len })
+}
+
+fn is_fence_close(rest: &str, open: &Fence) -> bool {
+    let (indent_len, indent_width): (usize, usize) = leading_indent(rest);
+    if indent_width > 3 {
+        return false;
+    }
+    let rest: &str = &rest[indent_len..];
+    let len: usize = rest
+        .bytes()
+        .take_while(|byte: &u8| *byte == open.marker)
+        .count();
+    len >= open.len && is_blank(&rest[len..])
+}
+
+/// Whether this line is the delimiter row of a table, e.g. `| :--- | ---: |`.
+fn is_table_delimiter_row(line: &str) -> bool {
+    let line: &str = line[quote_prefix_len(line)..].trim();
+    let line: &str = line.strip_prefix('|').unwrap_or(line);
+    let line: &str = line.strip_suffix('|').unwrap_or(line);
+    !line.is_empty()
+        && line.split('|').all(|cell: &str| {
+            let cell: &str = cell.trim();
+            let cell: &str = cell.strip_prefix(':').unwrap_or(cell);
+            let cell: &str = cell.strip_suffix(':').unwrap_or(cell);
+            !cell.is_empty() && cell.bytes().all(|byte: u8| byte == b'-')
+        })
+}
+
+fn is_atx_heading(rest: &str) -> bool {
+    let level: usize = rest.bytes().take_while(|byte: &u8| *byte == b'#').count();
+    (1..=6).contains(&level)
+        && rest[level..]
+            .bytes()
+            .next()
+            .map_or(true, |byte| byte == b' ' || byte == b'\t')
+}
+
+/// The byte length of a list item marker and the whitespace after it.
+fn list_marker_len(rest: &str) -> usize {
+    let bytes: &[u8] = rest.as_bytes();
+    let marker_len: usize = match bytes.first() {
+        Some(b'-' | b'*' | b'+') => 1,
+        Some(b'0'..=b'9') => {
+            let digits: usize = bytes
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            match bytes.get(digits) {
+                Some(b'.' | b')') if digits <= 9 => digits + 1,
+                _ => return 0,
+            }
+        }
+        _ => return 0,
+    };
+    match bytes.get(marker_len) {
+        None => marker_len,
+        Some(b' ' | b'\t') => marker_len + leading_indent(&rest[marker_len..]).0,
+        Some(_) => 0,
+    }
+}
+
+/// Sift a line of prose, leaving code spans untouched.
+fn sift_prose(content: &str, out: &mut String) {
+    let bytes: &[u8] = content.as_bytes();
+    let mut ind: usize = 0;
+    let mut is_start: bool = true;
+    let mut pending_whitespace: Option<char> = None;
+    while ind < bytes.len() {
+        let end: usize = match bytes[ind] {
+            byte @ (b' ' | b'\t' | b'\x0C' | CARRIAGE_RETURN) => {
+                if !is_start && pending_whitespace.is_none() {
+                    pending_whitespace = Some(byte as char);
+                }
+                ind += 1;
+                continue;
+            }
+            b'`' => {
+                let run: usize = bytes[ind..]
+                    .iter()
+                    .take_while(|byte| **byte == b'`')
+                    .count();
+                find_backtick_run(bytes, ind + run, run).map_or(ind + run, |end| end + run)
+            }
+            _ => ind + content[ind..].chars().next().map_or(1, char::len_utf8),
+        };
+        if let Some(whitespace) = pending_whitespace.take() {
+            out.push(whitespace);
+        }
+        out.push_str(&content[ind..end]);
+        ind = end;
+        is_start = false;
+    }
+}
+
+/// The start of the next run of exactly `len` backticks, closing a code span.
+fn find_backtick_run(bytes: &[u8], mut ind: usize, len: usize) -> Option<usize> {
+    while ind < bytes.len() {
+        if bytes[ind] != b'`' {
+            ind += 1;
+            continue;
+        }
+        let run: usize = bytes[ind..]
+            .iter()
+            .take_while(|byte| **byte == b'`')
+            .count();
+        if run == len {
+            return Some(ind);
+        }
+        ind += run;
+    }
+    None
+}
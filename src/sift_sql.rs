@@ -0,0 +1,154 @@
+use crate::LINE_FEED;
+
+/// What `sift_sql` does with comments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlComments {
+    /// Keep `--` and `/* */` comments verbatim.
+    Keep,
+    /// Remove comments, treating each of them as a whitespace.
+    Strip,
+}
+
+/// The SQL dialect `sift_sql` tokenizes, which decides where literals and comments end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqlDialect {
+    /// Standard SQL, where only a doubled quote escapes a quote.
+    /// PostgreSQL `E'escaped'` strings also escape with backslashes.
+    Standard,
+    /// MySQL, where backslashes escape within quotes, `` `identifiers` `` are quoted with backticks and `#` starts a comment.
+    MySql,
+}
+
+/// Collects tokens, separating them by at most one whitespace.
+struct TokenWriter<'a> {
+    out: &'a mut Vec<u8>,
+    is_start: bool,
+    pending_whitespace: Option<u8>,
+}
+
+impl TokenWriter<'_> {
+    fn push_whitespace(&mut self) {
+        if !self.is_start && self.pending_whitespace.is_none() {
+            self.pending_whitespace = Some(b' ');
+        }
+    }
+
+    fn push_token(&mut self, token: &[u8]) {
+        if let Some(whitespace) = self.pending_whitespace.take() {
+            self.out.push(whitespace);
+        }
+        self.out.extend_from_slice(token);
+        self.is_start = false;
+    }
+}
+
+/// A utility for `sift_sql`.
+pub(crate) fn sift_sql_preallocated(
+    input: &[u8],
+    comments: SqlComments,
+    dialect: SqlDialect,
+    out: &mut Vec<u8>,
+) {
+    let mut writer: TokenWriter = TokenWriter {
+        out,
+        is_start: true,
+        pending_whitespace: None,
+    };
+    let mut ind: usize = 0;
+    while ind < input.len() {
+        let byte: u8 = input[ind];
+        if byte.is_ascii_whitespace() {
+            writer.push_whitespace();
+            ind += 1;
+            continue;
+        }
+        let is_mysql: bool = dialect == SqlDialect::MySql;
+        let is_line_comment: bool =
+            (byte == b'-' && input.get(ind + 1) == Some(&b'-')) || (byte == b'#' && is_mysql);
+        let end: usize = match byte {
+            _ if is_line_comment => {
+                let end: usize = find(input, ind, b"\n").unwrap_or(input.len());
+                if comments == SqlComments::Strip {
+                    writer.push_whitespace();
+                } else {
+                    writer.push_token(&input[ind..end]);
+                    // The newline ending a line comment is the only whitespace that can follow it.
+                    writer.pending_whitespace = Some(LINE_FEED);
+                }
+                ind = end;
+                continue;
+            }
+            b'/' if input.get(ind + 1) == Some(&b'*') => {
+                let end: usize = find(input, ind + 2, b"*/").map_or(input.len(), |end| end + 2);
+                if comments == SqlComments::Strip {
+                    writer.push_whitespace();
+                } else {
+                    writer.push_token(&input[ind..end]);
+                }
+                ind = end;
+                continue;
+            }
+            b'\'' | b'"' => quoted_end(input, ind, byte, is_mysql),
+            b'`' if is_mysql => quoted_end(input, ind, byte, false),
+            b'E' | b'e'
+                if input.get(ind + 1) == Some(&b'\'')
+                    && (ind == 0 || !is_identifier(&input[ind - 1])) =>
+            {
+                quoted_end(input, ind + 1, b'\'', true)
+            }
+            b'$' => dollar_quoted_end(input, ind).unwrap_or(ind + 1),
+            _ => ind + 1,
+        };
+        writer.push_token(&input[ind..end]);
+        ind = end;
+    }
+}
+
+/// The end of a literal enclosed in `quote`, where a doubled `quote` is escaped.
+/// With `is_backslash_escaped`, a backslash escapes the byte after it too.
+fn quoted_end(input: &[u8], start: usize, quote: u8, is_backslash_escaped: bool) -> usize {
+    let mut ind: usize = start + 1;
+    while ind < input.len() {
+        if is_backslash_escaped && input[ind] == b'\\' {
+            ind += 1;
+        } else if input[ind] == quote {
+            if input.get(ind + 1) != Some(&quote) {
+                return ind + 1;
+            }
+            ind += 1;
+        }
+        ind += 1;
+    }
+    input.len()
+}
+
+/// The end of a dollar-quoted string like `$tag$ ... $tag$`, if one starts here.
+fn dollar_quoted_end(input: &[u8], start: usize) -> Option<usize> {
+    if start > 0 && (is_identifier(&input[start - 1]) || input[start - 1] == b'$') {
+        return None;
+    }
+    let tag_len: usize = input[start + 1..]
+        .iter()
+        .take_while(|byte| is_identifier(byte))
+        .count();
+    let delimiter_end: usize = start + 1 + tag_len + 1;
+    if input.get(start + 1).is_some_and(u8::is_ascii_digit)
+        || input.get(delimiter_end - 1) != Some(&b'$')
+    {
+        return None;
+    }
+    let delimiter: &[u8] = &input[start..delimiter_end];
+    Some(find(input, delimiter_end, delimiter).map_or(input.len(), |end| end + delimiter.len()))
+}
+
+/// Whether `byte` may be part of an unquoted identifier or keyword.
+const fn is_identifier(byte: &u8) -> bool {
+    byte.is_ascii_alphanumeric() || *byte == b'_' || *byte >= 0x80
+}
+
+fn find(input: &[u8], start: usize, needle: &[u8]) -> Option<usize> {
+    input[start..]
+        .windows(needle.len())
+        .position(|window: &[u8]| window == needle)
+        .map(|position: usize| start + position)
+}
@@ -0,0 +1,314 @@
+use crate::LINE_FEED;
+
+/// A configurable alternative to [`WhitespaceSifter`](crate::WhitespaceSifter).
+/// This classifies whole `char`s, so it is slower than the byte-wise [`WhitespaceSifter`](crate::WhitespaceSifter).
+/// With no options enabled, this sifts exactly like [`WhitespaceSifter`](crate::WhitespaceSifter).
+///
+/// # Examples
+///
+/// ```rust
+/// use whitespace_sifter::Sifter;
+/// let sifter: Sifter = Sifter::new().canonicalize_spaces(true).remove_invisibles(true);
+/// assert_eq!(sifter.sift("a\u{A0}\u{A0}b\u{200B}c"), "a bc");
+/// ```
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sifter {
+    pattern_white_space: bool,
+    canonicalize_spaces: bool,
+    remove_invisibles: bool,
+    controls: [ControlPolicy; CONTROL_COUNT],
+    unicode_newlines: bool,
+    line_ending: LineEnding,
+}
+
+/// The newline a [`Sifter`] emits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Keep each newline as it is in the input.
+    Keep,
+    /// `LF`, used by Unix-like systems.
+    Lf,
+    /// `CR-LF`, used by Windows.
+    CrLf,
+}
+
+/// What a [`Sifter`] does with a control character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlPolicy {
+    /// Keep the control character as content.
+    Keep,
+    /// Remove the control character.
+    Remove,
+    /// Treat the control character as a whitespace, replaced by an ASCII space whenever it is kept.
+    Whitespace,
+}
+
+/// A class of [control characters](https://www.unicode.org/charts/PDF/U0000.pdf).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlClass {
+    /// `U+0000` to `U+001F`, like `NUL`, backspace, vertical tab and escape.
+    C0,
+    /// `U+007F`.
+    Delete,
+    /// `U+0080` to `U+009F`.
+    C1,
+}
+
+/// The C0 controls, `DEL` and the C1 controls.
+const CONTROL_COUNT: usize = 32 + 1 + 32;
+
+impl Default for Sifter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How a `char` takes part in sifting.
+enum Class<'a> {
+    Whitespace(&'a str),
+    Newline(&'a str),
+    Removed,
+    Content,
+}
+
+impl Sifter {
+    /// A `Sifter` that follows the [is_ascii_whitespace](https://doc.rust-lang.org/std/primitive.char.html#method.is_ascii_whitespace) implementation.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            pattern_white_space: false,
+            canonicalize_spaces: false,
+            remove_invisibles: false,
+            controls: [ControlPolicy::Keep; CONTROL_COUNT],
+            unicode_newlines: false,
+            line_ending: LineEnding::Keep,
+        }
+    }
+
+    /// A `Sifter` that follows Unicode's [Pattern_White_Space](https://www.unicode.org/reports/tr31/#R3a), like the [Rust lexer](https://doc.rust-lang.org/reference/whitespace.html).
+    /// This adds vertical tabs, `NEL`, left-to-right and right-to-left marks, and the line and paragraph separators to the whitespaces of [`Sifter::new`].
+    #[must_use]
+    pub const fn pattern_white_space() -> Self {
+        let mut sifter: Self = Self::new();
+        sifter.pattern_white_space = true;
+        sifter
+    }
+
+    /// This treats Unicode [space separators](https://www.compart.com/en/unicode/category/Zs), like `NBSP` and thin spaces, as whitespaces.
+    /// These are replaced by an ASCII space whenever they are kept.
+    #[must_use]
+    pub const fn canonicalize_spaces(mut self, enabled: bool) -> Self {
+        self.canonicalize_spaces = enabled;
+        self
+    }
+
+    /// This removes invisible formatting characters: zero-width spaces, word joiners, soft hyphens and byte order marks.
+    /// This also removes zero-width joiners and non-joiners, unless they sit between two non-ASCII characters.
+    /// Such sequences are emoji or scripts like Arabic and Devanagari, where the joiners are meaningful.
+    #[must_use]
+    pub const fn remove_invisibles(mut self, enabled: bool) -> Self {
+        self.remove_invisibles = enabled;
+        self
+    }
+
+    /// This treats `NEL` (`U+0085`), `LINE SEPARATOR` (`U+2028`) and `PARAGRAPH SEPARATOR` (`U+2029`) as newlines.
+    /// These are then preserved and deduplicated by [`Sifter::sift_preserve_newlines`] like `LF` and `CR-LF`.
+    #[must_use]
+    pub const fn unicode_newlines(mut self, enabled: bool) -> Self {
+        self.unicode_newlines = enabled;
+        self
+    }
+
+    /// This replaces every newline that is kept with `line_ending`.
+    #[must_use]
+    pub const fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// This sets the policy for every control character in `class`.
+    /// Control characters that are already whitespaces, like tabs and newlines, are not affected.
+    #[must_use]
+    pub const fn control_class(mut self, class: ControlClass, policy: ControlPolicy) -> Self {
+        let (start, end): (usize, usize) = match class {
+            ControlClass::C0 => (0, 32),
+            ControlClass::Delete => (32, 33),
+            ControlClass::C1 => (33, CONTROL_COUNT),
+        };
+        let mut ind: usize = start;
+        while ind < end {
+            self.controls[ind] = policy;
+            ind += 1;
+        }
+        self
+    }
+
+    /// This sets the policy for a single control character.
+    /// Control characters that are already whitespaces, like tabs and newlines, are not affected.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `control` is not a control character.
+    #[must_use]
+    pub const fn control(mut self, control: char, policy: ControlPolicy) -> Self {
+        match control_index(control) {
+            Some(ind) => self.controls[ind] = policy,
+            None => panic!("not a control character"),
+        }
+        self
+    }
+
+    /// This removes duplicate whitespaces from a `string`, like [`WhitespaceSifter::sift`](crate::WhitespaceSifter::sift).
+    #[must_use]
+    pub fn sift(&self, input: &str) -> String {
+        self.sift_with(input, false)
+    }
+
+    /// This removes duplicate whitespaces from a `string` and preserves deduplicated newlines, like [`WhitespaceSifter::sift_preserve_newlines`](crate::WhitespaceSifter::sift_preserve_newlines).
+    #[must_use]
+    pub fn sift_preserve_newlines(&self, input: &str) -> String {
+        self.sift_with(input, true)
+    }
+
+    fn sift_with(self, input: &str, preserve_newlines: bool) -> String {
+        let mut out: String = String::with_capacity(input.len());
+        let mut is_start: bool = true;
+        let mut pending: Option<&str> = None;
+        let mut is_pending_newline: bool = false;
+        let mut is_pending_carriage_return: bool = false;
+        let mut prev: Option<char> = None;
+        let mut ind: usize = 0;
+        while let Some(ch) = input[ind..].chars().next() {
+            let (class, len): (Class, usize) =
+                self.classify(input, ind, ch, prev, preserve_newlines);
+            prev = Some(ch);
+            match class {
+                Class::Whitespace(unit) => {
+                    if !is_start && pending.is_none() {
+                        pending = Some(unit);
+                        is_pending_carriage_return = unit == "\r";
+                    } else if is_pending_carriage_return
+                        && input.as_bytes()[ind + len - 1] == LINE_FEED
+                    {
+                        // Like `WhitespaceSifter::sift`, a lone carriage return keeps the first line feed after it.
+                        pending = Some(match self.line_ending {
+                            LineEnding::Lf => "\n",
+                            LineEnding::Keep | LineEnding::CrLf => "\r\n",
+                        });
+                        is_pending_carriage_return = false;
+                    }
+                }
+                Class::Newline(unit) => {
+                    if !is_start && !is_pending_newline {
+                        pending = Some(unit);
+                        is_pending_newline = true;
+                    }
+                }
+                Class::Removed => {}
+                Class::Content => {
+                    if let Some(unit) = pending.take() {
+                        out.push_str(unit);
+                    }
+                    is_pending_newline = false;
+                    is_pending_carriage_return = false;
+                    out.push_str(&input[ind..ind + len]);
+                    is_start = false;
+                }
+            }
+            ind += len;
+        }
+        out
+    }
+
+    /// Classify the `char` at `ind`, returning how many bytes it spans.
+    fn classify<'a>(
+        self,
+        input: &'a str,
+        ind: usize,
+        ch: char,
+        prev: Option<char>,
+        preserve_newlines: bool,
+    ) -> (Class<'a>, usize) {
+        let len: usize = ch.len_utf8();
+        let newline_len: usize = match ch {
+            '\n' => 1,
+            '\r' if input.as_bytes().get(ind + 1) == Some(&LINE_FEED) => 2,
+            '\u{85}' | '\u{2028}' | '\u{2029}' if self.unicode_newlines => len,
+            _ => 0,
+        };
+        if newline_len > 0 {
+            let unit: &'a str = match self.line_ending {
+                LineEnding::Keep => &input[ind..ind + newline_len],
+                LineEnding::Lf => "\n",
+                LineEnding::CrLf => "\r\n",
+            };
+            let class: Class<'a> = if preserve_newlines {
+                Class::Newline(unit)
+            } else {
+                Class::Whitespace(unit)
+            };
+            return (class, newline_len);
+        }
+        let unit: &'a str = &input[ind..ind + len];
+        let class: Class<'a> = match ch {
+            ' ' | '\t' | '\x0C' | '\r' => Class::Whitespace(unit),
+            '\x0B' | '\u{85}' | '\u{200E}' | '\u{200F}' | '\u{2028}' | '\u{2029}'
+                if self.pattern_white_space =>
+            {
+                Class::Whitespace(unit)
+            }
+            _ if self.canonicalize_spaces && is_space_separator(ch) => Class::Whitespace(" "),
+            _ if self.remove_invisibles && is_invisible(ch) => Class::Removed,
+            '\u{200C}' | '\u{200D}' if self.remove_invisibles => {
+                let next: Option<char> = input[ind + len..].chars().next();
+                if is_joinable(prev) && is_joinable(next) {
+                    Class::Content
+                } else {
+                    Class::Removed
+                }
+            }
+            _ => match control_index(ch) {
+                Some(ind) => match self.controls[ind] {
+                    ControlPolicy::Keep => Class::Content,
+                    ControlPolicy::Remove => Class::Removed,
+                    ControlPolicy::Whitespace => Class::Whitespace(" "),
+                },
+                None => Class::Content,
+            },
+        };
+        (class, len)
+    }
+}
+
+/// The index of a control character within [`Sifter`]'s policies.
+const fn control_index(ch: char) -> Option<usize> {
+    match ch {
+        '\0'..='\x1F' => Some(ch as usize),
+        '\x7F' => Some(32),
+        '\u{80}'..='\u{9F}' => Some(ch as usize - 0x80 + 33),
+        _ => None,
+    }
+}
+
+/// Unicode's [Space_Separator](https://www.compart.com/en/unicode/category/Zs) category.
+const fn is_space_separator(ch: char) -> bool {
+    matches!(ch, '\u{2000}'..='\u{200A}')
+        || matches!(
+            ch,
+            ' ' | '\u{A0}' | '\u{1680}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+        )
+}
+
+/// Invisible formatting characters that never carry meaning within text.
+const fn is_invisible(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{AD}' | '\u{180E}' | '\u{200B}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+/// Whether a zero-width joiner or non-joiner next to this `char` may be meaningful.
+fn is_joinable(ch: Option<char>) -> bool {
+    ch.is_some_and(|ch: char| !ch.is_ascii() && !ch.is_whitespace() && !is_invisible(ch))
+}
@@ -0,0 +1,107 @@
+use crate::test_inputs::all_strings;
+use crate::{ControlClass, ControlPolicy, LineEnding, Sifter, WhitespaceSifter};
+
+#[test]
+fn test_sifter_matches_whitespace_sifter() {
+    let sifter: Sifter = Sifter::new();
+    for input in all_strings(&["a", " ", "\t", "\r", "\n", "\x0C", "❤"], 6) {
+        assert_eq!(sifter.sift(&input), input.sift(), "{input:?}");
+        assert_eq!(
+            sifter.sift_preserve_newlines(&input),
+            input.sift_preserve_newlines(),
+            "{input:?}"
+        );
+    }
+}
+
+#[test]
+fn test_sifter_canonicalize_spaces() {
+    let sifter: Sifter = Sifter::new().canonicalize_spaces(true);
+    assert_eq!(
+        sifter.sift("\u{A0}a\u{2009}\u{A0} b\u{3000}c\u{202F}"),
+        "a b c"
+    );
+    assert_eq!(
+        sifter.sift_preserve_newlines("a\u{A0}\n\u{2003}b\u{A0}\tc"),
+        "a\nb c"
+    );
+}
+
+#[test]
+fn test_sifter_remove_invisibles() {
+    let sifter: Sifter = Sifter::new().remove_invisibles(true);
+    assert_eq!(
+        sifter.sift("\u{FEFF}zero\u{200B}width \u{200B} soft\u{AD}hyphen\u{FEFF}"),
+        "zerowidth softhyphen"
+    );
+    // Emoji sequences and Persian keep their joiners, misused joiners are removed.
+    assert_eq!(
+        sifter.sift("👨\u{200D}👩\u{200D}👧"),
+        "👨\u{200D}👩\u{200D}👧"
+    );
+    assert_eq!(sifter.sift("🏳\u{FE0F}\u{200D}🌈"), "🏳\u{FE0F}\u{200D}🌈");
+    assert_eq!(sifter.sift("می\u{200C}خواهم"), "می\u{200C}خواهم");
+    assert_eq!(sifter.sift("a\u{200D}b \u{200C}c\u{200C}"), "ab c");
+}
+
+#[test]
+fn test_sifter_controls() {
+    let sifter: Sifter = Sifter::new()
+        .control_class(ControlClass::C0, ControlPolicy::Remove)
+        .control_class(ControlClass::C1, ControlPolicy::Whitespace)
+        .control('\x0B', ControlPolicy::Whitespace)
+        .control('\x1B', ControlPolicy::Keep);
+    assert_eq!(
+        sifter.sift("\0a\x08b \x0B c\u{85}\u{9F}d\x1B[0m\x7F\t\x00"),
+        "ab c d\x1B[0m\x7F"
+    );
+    assert_eq!(
+        sifter.sift_preserve_newlines("a\x0B\n\x0Bb\x01\x0B\u{80}c"),
+        "a\nb c"
+    );
+}
+
+#[test]
+#[should_panic(expected = "not a control character")]
+fn test_sifter_control_panics() {
+    let _ = Sifter::new().control('a', ControlPolicy::Remove);
+}
+
+#[test]
+fn test_sifter_unicode_newlines() {
+    let input: &str = " a\u{2028}\u{2028} b \u{85}\n\u{2029}c\r\n\u{85}d\u{2029}";
+    let sifter: Sifter = Sifter::new().unicode_newlines(true);
+    assert_eq!(
+        sifter.sift_preserve_newlines(input),
+        "a\u{2028}b\u{85}c\r\nd"
+    );
+    assert_eq!(sifter.sift(input), "a\u{2028}b c\r\nd");
+    assert_eq!(
+        sifter
+            .line_ending(LineEnding::Lf)
+            .sift_preserve_newlines(input),
+        "a\nb\nc\nd"
+    );
+    assert_eq!(
+        sifter
+            .line_ending(LineEnding::CrLf)
+            .sift_preserve_newlines(input),
+        "a\r\nb\r\nc\r\nd"
+    );
+    assert_eq!(
+        Sifter::new().sift_preserve_newlines(input),
+        "a\u{2028}\u{2028} b \u{85}\n\u{2029}c\r\n\u{85}d\u{2029}"
+    );
+}
+
+#[test]
+fn test_sifter_pattern_white_space() {
+    let input: &str = "\u{200E} a\x0B\x0B\u{85}b\u{200F}\u{2028} c\u{2029}\u{A0}d \u{200E}";
+    let sifter: Sifter = Sifter::pattern_white_space();
+    assert_eq!(sifter.sift(input), "a\x0Bb\u{200F}c\u{2029}\u{A0}d");
+    assert_eq!(
+        sifter.unicode_newlines(true).sift_preserve_newlines(input),
+        "a\u{85}b\u{2028}c\u{2029}\u{A0}d"
+    );
+    assert_eq!(Sifter::new().sift(input), input.sift());
+}
@@ -0,0 +1,66 @@
+use crate::{SqlComments, SqlDialect, WhitespaceSifter};
+
+#[test]
+fn test_sift_sql() {
+    let input: &str =
+        "\n  SELECT  a,\n\t\"Quoted   Column\"\r\n  FROM t\n WHERE  b = 'it''s   here'  \n";
+    let out: &str = "SELECT a, \"Quoted   Column\" FROM t WHERE b = 'it''s   here'";
+    assert_eq!(input.sift_sql(SqlComments::Keep, SqlDialect::Standard), out);
+    assert_eq!(
+        "SELECT a, \"Quoted   Column\"   FROM t\nWHERE b =\n'it''s   here'"
+            .sift_sql(SqlComments::Keep, SqlDialect::Standard),
+        out
+    );
+}
+
+#[test]
+fn test_sift_sql_dollar_quotes() {
+    let input: &str =
+        "CREATE FUNCTION f($1  int) AS $body$\n  SELECT  $$ a  b $$;\n$body$   LANGUAGE  sql";
+    let out: &str =
+        "CREATE FUNCTION f($1 int) AS $body$\n  SELECT  $$ a  b $$;\n$body$ LANGUAGE sql";
+    assert_eq!(input.sift_sql(SqlComments::Keep, SqlDialect::Standard), out);
+    assert_eq!(
+        &"SELECT  a$b$  FROM t".sift_sql(SqlComments::Keep, SqlDialect::Standard),
+        "SELECT a$b$ FROM t"
+    );
+}
+
+#[test]
+fn test_sift_sql_comments() {
+    let input: &str =
+        "-- header\nSELECT  a /* the   a */ ,  b -- trailing   note\n   FROM t /* unterminated  ";
+    assert_eq!(
+        input.sift_sql(SqlComments::Keep, SqlDialect::Standard),
+        "-- header\nSELECT a /* the   a */ , b -- trailing   note\nFROM t /* unterminated  "
+    );
+    assert_eq!(
+        input.sift_sql(SqlComments::Strip, SqlDialect::Standard),
+        "SELECT a , b FROM t"
+    );
+    assert_eq!(
+        &"a/**/b--c\nd".sift_sql(SqlComments::Strip, SqlDialect::Standard),
+        "a b d"
+    );
+}
+
+#[test]
+fn test_sift_sql_backslash_escapes() {
+    let input: &str = "SELECT  'it\\'s  a' ,   `my  col`  # note  here\n FROM  \"a\\\"  b\"";
+    assert_eq!(
+        input.sift_sql(SqlComments::Keep, SqlDialect::MySql),
+        "SELECT 'it\\'s  a' , `my  col` # note  here\nFROM \"a\\\"  b\""
+    );
+    assert_eq!(
+        input.sift_sql(SqlComments::Strip, SqlDialect::MySql),
+        "SELECT 'it\\'s  a' , `my  col` FROM \"a\\\"  b\""
+    );
+    assert_eq!(
+        &"SELECT  E'it\\'s  a' ,   'C:\\'  ,  b".sift_sql(SqlComments::Keep, SqlDialect::Standard),
+        "SELECT E'it\\'s  a' , 'C:\\' , b"
+    );
+    assert_eq!(
+        &"SELECT  TYPE'a\\'  ,  'b'".sift_sql(SqlComments::Keep, SqlDialect::Standard),
+        "SELECT TYPE'a\\' , 'b'"
+    );
+}
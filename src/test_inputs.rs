@@ -0,0 +1,17 @@
+/// Every string of at most `max_len` pieces of `alphabet`, for exhaustively comparing implementations.
+pub(crate) fn all_strings(alphabet: &[&str], max_len: usize) -> Vec<String> {
+    let mut strings: Vec<String> = vec![String::new()];
+    let mut longest: Vec<String> = vec![String::new()];
+    for _ in 0..max_len {
+        longest = longest
+            .iter()
+            .flat_map(|string: &String| {
+                alphabet
+                    .iter()
+                    .map(move |piece: &&str| string.clone() + piece)
+            })
+            .collect();
+        strings.extend_from_slice(&longest);
+    }
+    strings
+}
@@ -66,3 +66,11 @@ fn test_docs() {
         "1..\n2..\n3..\n4..\r\n5.."
     );
 }
+
+// Regression tests for bugs in published versions, which the compliance check skips.
+
+#[test]
+fn test_sift_preserve_newlines_lone_carriage_return() {
+    assert_eq!(&"a\rb".sift_preserve_newlines(), "a\rb");
+    assert_eq!(&"a\r\n".sift_preserve_newlines(), "a");
+}
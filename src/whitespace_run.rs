@@ -0,0 +1,118 @@
+use crate::{get_char_metadata, Character, CARRIAGE_RETURN, LINE_FEED};
+use std::ops::Range;
+
+/// A run of consecutive whitespaces, where `CR-LF` counts as one whitespace.
+pub(crate) struct WhitespaceRun {
+    /// The byte range of the whole run.
+    pub(crate) run: Range<usize>,
+    /// The byte range of the one whitespace that sifting keeps.
+    pub(crate) keep: Range<usize>,
+}
+
+/// Find the next run of whitespaces at or after `ind`, leaving `ind` at its end.
+/// This keeps the first whitespace of the run, or its first newline if `preserve_newlines` is set.
+pub(crate) fn next_whitespace_run(
+    in_ptr: *const u8,
+    in_len: usize,
+    ind: &mut usize,
+    preserve_newlines: bool,
+) -> Option<WhitespaceRun> {
+    while *ind < in_len {
+        match get_char_metadata(unsafe { in_ptr.add(*ind).read() }) {
+            Character::SingleByte => *ind = unsafe { ind.unchecked_add(1) },
+            Character::MultiByte { len } => *ind = unsafe { ind.unchecked_add(len as usize) },
+            _ => break,
+        }
+    }
+    if *ind >= in_len {
+        return None;
+    }
+    let start: usize = *ind;
+    let mut keep: Option<Range<usize>> = None;
+    let mut is_keep_newline: bool = false;
+    while *ind < in_len {
+        let unit_start: usize = *ind;
+        let is_newline: bool = match get_char_metadata(unsafe { in_ptr.add(*ind).read() }) {
+            Character::NormalWhitespace => {
+                *ind = unsafe { ind.unchecked_add(1) };
+                false
+            }
+            Character::LineFeed => {
+                *ind = unsafe { ind.unchecked_add(1) };
+                true
+            }
+            Character::CarriageReturn => {
+                *ind = unsafe { ind.unchecked_add(1) };
+                if *ind < in_len && unsafe { in_ptr.add(*ind).read() } == LINE_FEED {
+                    *ind = unsafe { ind.unchecked_add(1) };
+                    true
+                } else {
+                    false
+                }
+            }
+            Character::SingleByte | Character::MultiByte { .. } => break,
+        };
+        if keep.is_none() || (preserve_newlines && is_newline && !is_keep_newline) {
+            keep = Some(unit_start..*ind);
+            is_keep_newline = is_newline;
+        }
+    }
+    Some(WhitespaceRun {
+        run: start..*ind,
+        keep: keep.unwrap_or(start..*ind),
+    })
+}
+
+/// A utility for `find_unsifted` and `find_unsifted_preserve_newlines`.
+pub(crate) fn find_unsifted_run(
+    in_ptr: *const u8,
+    in_len: usize,
+    preserve_newlines: bool,
+) -> Option<Range<usize>> {
+    let mut ind: usize = 0;
+    while let Some(WhitespaceRun { run, keep }) =
+        next_whitespace_run(in_ptr, in_len, &mut ind, preserve_newlines)
+    {
+        if run.start == 0 || run.end == in_len || run != keep {
+            return Some(run);
+        }
+    }
+    None
+}
+
+/// A utility for `sift_edits` and `sift_edits_preserve_newlines`.
+pub(crate) fn sift_edit_runs(input: &str, preserve_newlines: bool) -> Vec<(Range<usize>, &str)> {
+    let in_ptr: *const u8 = input.as_ptr();
+    let in_len: usize = input.len();
+    let mut edits: Vec<(Range<usize>, &str)> = Vec::new();
+    let mut ind: usize = 0;
+    while let Some(WhitespaceRun { run, keep }) =
+        next_whitespace_run(in_ptr, in_len, &mut ind, preserve_newlines)
+    {
+        if run.start == 0 || run.end == in_len {
+            edits.push((run, ""));
+            continue;
+        }
+        // `sift` keeps a lone carriage-return along with the first line-feed after it.
+        let line_feed: Option<usize> = if !preserve_newlines
+            && keep.len() == 1
+            && unsafe { in_ptr.add(keep.start).read() } == CARRIAGE_RETURN
+        {
+            input.as_bytes()[run.clone()]
+                .iter()
+                .position(|&byte: &u8| byte == LINE_FEED)
+                .map(|offset: usize| run.start + offset)
+        } else {
+            None
+        };
+        match line_feed {
+            Some(line_feed) if line_feed + 1 == run.end => edits.push((keep.end..line_feed, "")),
+            Some(line_feed) => edits.push((keep.end..run.end, &input[line_feed..=line_feed])),
+            None if keep == run => {}
+            None if keep.start == run.start => edits.push((keep.end..run.end, "")),
+            None if keep.end == run.end => edits.push((run.start..keep.start, "")),
+            None => edits.push((run, &input[keep])),
+        }
+    }
+    edits
+}